@@ -0,0 +1,227 @@
+use crate::{
+    can::{CanBus, CanFrame, CanTransport, CAN_MAX_DATA_LENGTH},
+    error::WitResult,
+    sensor::WitSensor,
+    serial::WitSerial,
+};
+
+/// Start of an AT framed CAN message
+pub const AT_FRAME_HEADER: &[u8; 2] = b"AT";
+
+/// End of an AT framed CAN message
+pub const AT_FRAME_TRAILER: &[u8; 2] = b"\r\n";
+
+/// Bytes before the payload: header, frame ID (4) and length (2)
+const AT_PREFIX_LENGTH: usize = 8;
+
+/// Frame ID flag for 29-bit extended identifiers
+const FRAME_ID_EXTENDED: u32 = 0x04;
+
+/// Frame ID flag for remote frames
+const FRAME_ID_REMOTE: u32 = 0x02;
+
+/// Pack a CAN identifier the way WitMotion USB-CAN adapters expect it
+/// Standard IDs sit in the top 11 bits, extended IDs are shifted by 3 with flag 0x04
+pub fn at_frame_id(id: u32, extended: bool) -> u32 {
+    if extended {
+        ((id & 0x1FFF_FFFF) << 3) | FRAME_ID_EXTENDED
+    } else {
+        (id & 0x7FF) << 21
+    }
+}
+
+/// Encode a CAN frame as `"AT" + frame ID (LE) + length (BE) + data + "\r\n"`
+pub fn encode_at_frame(frame: &CanFrame) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(AT_PREFIX_LENGTH + frame.data.len() + 2);
+    bytes.extend_from_slice(AT_FRAME_HEADER);
+    bytes.extend_from_slice(&at_frame_id(frame.id, frame.extended).to_le_bytes());
+    bytes.extend_from_slice(&(frame.data.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&frame.data);
+    bytes.extend_from_slice(AT_FRAME_TRAILER);
+    bytes
+}
+
+/// Decoder for AT framed CAN messages received from a USB-CAN adapter
+#[derive(Default)]
+pub struct AtFrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl AtFrameDecoder {
+    /// Create a new decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Process a received byte and return a frame once one is complete
+    /// Remote frames are dropped, the sensor only sends data frames
+    pub fn process_byte(&mut self, byte: u8) -> Option<CanFrame> {
+        self.buffer.push(byte);
+
+        loop {
+            // Resynchronize on the "AT" header
+            if !self.buffer.is_empty() && self.buffer[0] != AT_FRAME_HEADER[0] {
+                self.buffer.remove(0);
+                continue;
+            }
+            if self.buffer.len() >= 2 && self.buffer[1] != AT_FRAME_HEADER[1] {
+                self.buffer.remove(0);
+                continue;
+            }
+            if self.buffer.len() < AT_PREFIX_LENGTH {
+                return None;
+            }
+
+            let length = u16::from_be_bytes([self.buffer[6], self.buffer[7]]) as usize;
+            if length > CAN_MAX_DATA_LENGTH {
+                self.buffer.remove(0);
+                continue;
+            }
+
+            let total = AT_PREFIX_LENGTH + length + AT_FRAME_TRAILER.len();
+            if self.buffer.len() < total {
+                return None;
+            }
+            if &self.buffer[total - 2..total] != AT_FRAME_TRAILER {
+                self.buffer.remove(0);
+                continue;
+            }
+
+            let frame_id = u32::from_le_bytes([
+                self.buffer[2],
+                self.buffer[3],
+                self.buffer[4],
+                self.buffer[5],
+            ]);
+            let data = self.buffer[AT_PREFIX_LENGTH..AT_PREFIX_LENGTH + length].to_vec();
+            self.buffer.drain(..total);
+
+            if frame_id & FRAME_ID_REMOTE != 0 {
+                continue;
+            }
+
+            let extended = frame_id & FRAME_ID_EXTENDED != 0;
+            let id = if extended { frame_id >> 3 } else { frame_id >> 21 };
+            return Some(CanFrame { id, extended, data });
+        }
+    }
+
+    /// Drop any partially received frame
+    pub fn clear_buffer(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+/// CAN interface provided by a WitMotion USB/TTL-to-CAN adapter on a serial port
+pub struct AtCanBus {
+    serial: WitSerial,
+    decoder: AtFrameDecoder,
+}
+
+impl AtCanBus {
+    /// Use an open serial port connected to the adapter
+    pub fn new(serial: WitSerial) -> Self {
+        Self {
+            serial,
+            decoder: AtFrameDecoder::new(),
+        }
+    }
+
+    /// Open the adapter's serial port
+    pub fn open(device_path: &str, baud_rate: u32) -> WitResult<Self> {
+        Ok(Self::new(WitSerial::open(device_path, baud_rate)?))
+    }
+
+    /// Get the underlying serial port
+    pub fn serial(&self) -> &WitSerial {
+        &self.serial
+    }
+
+    /// Get the underlying serial port mutably
+    pub fn serial_mut(&mut self) -> &mut WitSerial {
+        &mut self.serial
+    }
+}
+
+impl CanBus for AtCanBus {
+    fn send(&mut self, frame: &CanFrame) -> WitResult<()> {
+        self.serial.write(&encode_at_frame(frame))?;
+        self.serial.flush()
+    }
+
+    fn receive(&mut self) -> WitResult<Option<CanFrame>> {
+        while let Some(byte) = self.serial.read_byte()? {
+            if let Some(frame) = self.decoder.process_byte(byte) {
+                return Ok(Some(frame));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl WitSensor<CanTransport<AtCanBus>> {
+    /// Open a CAN sensor behind a WitMotion USB-CAN adapter
+    /// `high_precision` selects the WT905x 32-bit angle frames
+    pub fn open_at_can(
+        device_path: &str,
+        baud_rate: u32,
+        can_id: u32,
+        high_precision: bool,
+    ) -> WitResult<Self> {
+        let bus = AtCanBus::open(device_path, baud_rate)?;
+        Ok(Self::with_transport(CanTransport::new(bus, can_id, high_precision)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{can::CanProtocol, registers::*};
+
+    #[test]
+    fn test_matches_csharp_sdk() {
+        let mut can = CanProtocol::new(0x50, false);
+
+        // WitCanProtocolUtils.GetStandRead(0x50, 0x34)
+        let read = encode_at_frame(&can.generate_read_request(AX));
+        assert_eq!(
+            read,
+            vec![
+                0x41, 0x54, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x05, 0xFF, 0xAA, 0x27, 0x34, 0x00,
+                0x0D, 0x0A,
+            ]
+        );
+
+        // WitCanProtocolUtils.GetStandWrite(0x50, 0x69, unchecked((short)0xB588))
+        let write = encode_at_frame(&can.generate_write_request(KEY, KEY_UNLOCK));
+        assert_eq!(
+            write,
+            vec![
+                0x41, 0x54, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x05, 0xFF, 0xAA, 0x69, 0x88, 0xB5,
+                0x0D, 0x0A,
+            ]
+        );
+
+        // WitCanProtocolUtils.GetFrameId(0x50, 1, 1)
+        assert_eq!(at_frame_id(0x50, true) | FRAME_ID_REMOTE, 0x286);
+    }
+
+    #[test]
+    fn test_decoder() {
+        let mut decoder = AtFrameDecoder::new();
+        let frame = CanFrame::new(0x50, &[0x55, 0x51, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00]).unwrap();
+        let extended = CanFrame {
+            id: 0x1234_5678,
+            extended: true,
+            data: vec![0x55, 0x53],
+        };
+
+        // Leading noise is skipped
+        let mut bytes = vec![0x00, 0x41, 0x0D];
+        bytes.extend(encode_at_frame(&frame));
+        bytes.extend(encode_at_frame(&extended));
+
+        let frames: Vec<_> = bytes.into_iter().filter_map(|b| decoder.process_byte(b)).collect();
+        assert_eq!(frames, vec![frame, extended]);
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{
+    error::{WitError, WitResult},
+    model::SensorModel,
+    registers::*,
+    sensor::WitSensor,
+    transaction::ConfigTransaction,
+    transport::Transport,
+};
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// Version written to and expected from snapshot files
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Writable registers a restore never touches: the link settings, the clock
+/// and the calibration command
+const NOT_RESTORED: &[u16] = &[CALSW, BAUD, IICADDR, YYMM, DDHH, MMSS, MS];
+
+/// Values of the whole register file (0..REGSIZE) of one sensor
+///
+/// The text form has a `version` and `model` header followed by one
+/// `0x1F = 0x0004  # BANDWIDTH` line per register, so snapshots diff well in git.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterSnapshot {
+    model: SensorModel,
+    values: BTreeMap<u16, u16>,
+}
+
+impl RegisterSnapshot {
+    /// Create an empty snapshot for a model
+    pub fn new(model: SensorModel) -> Self {
+        Self {
+            model,
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// Get the model the snapshot was taken from
+    pub fn model(&self) -> SensorModel {
+        self.model
+    }
+
+    /// Get the value of a register
+    pub fn get(&self, register: u16) -> Option<u16> {
+        self.values.get(&register).copied()
+    }
+
+    /// Set the value of a register
+    pub fn set(&mut self, register: u16, value: u16) -> WitResult<()> {
+        if register as usize >= REGSIZE {
+            return Err(WitError::InvalidParameter(format!("Invalid register: 0x{:02X}", register)));
+        }
+        self.values.insert(register, value);
+        Ok(())
+    }
+
+    /// Iterate over the registers in address order
+    pub fn iter(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.values.iter().map(|(&register, &value)| (register, value))
+    }
+
+    /// Number of registers in the snapshot
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Check whether the snapshot holds no registers
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The writes a restore with `filter` would make, in address order
+    pub fn restore_writes(&self, filter: &RestoreFilter) -> Vec<(u16, u16)> {
+        self.iter()
+            .filter(|&(register, _)| is_restorable(register) && filter.includes(register))
+            .collect()
+    }
+}
+
+impl fmt::Display for RegisterSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# WitMotion register snapshot")?;
+        writeln!(f, "version = {}", SNAPSHOT_VERSION)?;
+        writeln!(f, "model = {}", self.model)?;
+        for (register, value) in self.iter() {
+            let name = Register::from_address(register).map_or("", |r| r.name());
+            writeln!(f, "0x{:02X} = 0x{:04X}  # {}", register, value, name)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for RegisterSnapshot {
+    type Err = WitError;
+
+    fn from_str(s: &str) -> WitResult<Self> {
+        let mut version = None;
+        let mut snapshot: Option<RegisterSnapshot> = None;
+
+        for (number, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || WitError::InvalidParameter(format!("Invalid snapshot line {}: {}", number + 1, line));
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "version" => {
+                    let v: u32 = value.parse().map_err(|_| invalid())?;
+                    if v != SNAPSHOT_VERSION {
+                        return Err(WitError::InvalidParameter(format!("Unsupported snapshot version: {}", v)));
+                    }
+                    version = Some(v);
+                }
+                _ if version.is_none() => {
+                    return Err(WitError::InvalidParameter("Snapshot does not start with a version".to_string()));
+                }
+                "model" => snapshot = Some(RegisterSnapshot::new(value.parse()?)),
+                _ => {
+                    let snapshot = snapshot
+                        .as_mut()
+                        .ok_or_else(|| WitError::InvalidParameter("Snapshot has no model".to_string()))?;
+                    snapshot.set(parse_hex(key).ok_or_else(invalid)?, parse_hex(value).ok_or_else(invalid)?)?;
+                }
+            }
+        }
+
+        snapshot.ok_or_else(|| WitError::InvalidParameter("Snapshot has no model".to_string()))
+    }
+}
+
+/// Which configuration registers a restore writes
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RestoreFilter {
+    /// Every configuration register in the snapshot
+    #[default]
+    All,
+    /// Only the listed registers
+    Only(Vec<u16>),
+    /// Every configuration register except the listed ones
+    Except(Vec<u16>),
+}
+
+impl RestoreFilter {
+    /// Check whether the filter lets a register through
+    pub fn includes(&self, register: u16) -> bool {
+        match self {
+            RestoreFilter::All => true,
+            RestoreFilter::Only(registers) => registers.contains(&register),
+            RestoreFilter::Except(registers) => !registers.contains(&register),
+        }
+    }
+}
+
+impl<T: Transport> WitSensor<T> {
+    /// Read every register from 0 to REGSIZE
+    pub fn dump_all(&mut self) -> WitResult<RegisterSnapshot> {
+        let mut snapshot = RegisterSnapshot::new(self.profile().model);
+        let registers: Vec<u16> = (0..REGSIZE as u16).collect();
+        let values = self.read_register_values(&registers)?;
+        for (register, value) in registers.into_iter().zip(values) {
+            snapshot.set(register, value)?;
+        }
+
+        Ok(snapshot)
+    }
+
+    /// Write the configuration registers of a snapshot, verify them and save
+    ///
+    /// Measurements, identification, commands, the link settings (BAUD, IICADDR)
+    /// and the clock are never written. Returns the number of registers written.
+    pub fn restore(&mut self, snapshot: &RegisterSnapshot, filter: &RestoreFilter) -> WitResult<usize> {
+        let model = self.profile().model;
+        if snapshot.model() != model {
+            return Err(WitError::InvalidParameter(format!(
+                "Snapshot of a {} cannot be restored to a {}",
+                snapshot.model(),
+                model
+            )));
+        }
+
+        let mut transaction = ConfigTransaction::new();
+        for (register, value) in snapshot.restore_writes(filter) {
+            transaction.write(register, value);
+        }
+        transaction.commit(self)?;
+        Ok(transaction.writes().len())
+    }
+}
+
+/// Check whether a register holds configuration that can be restored
+fn is_restorable(register: u16) -> bool {
+    Register::from_address(register).is_some_and(|r| r.access() == RegisterAccess::ReadWrite)
+        && !NOT_RESTORED.contains(&register)
+}
+
+/// Parse a `0x`-prefixed hexadecimal number
+fn parse_hex(text: &str) -> Option<u16> {
+    let hex = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X"))?;
+    u16::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockTransport, protocol::WitProtocol};
+
+    #[test]
+    fn test_snapshot_round_trip_and_filter() {
+        let mut snapshot = RegisterSnapshot::new(SensorModel::Wt901C485);
+        snapshot.set(BAUD, 2).unwrap();
+        snapshot.set(BANDWIDTH, 4).unwrap();
+        snapshot.set(ACCRANGE, 3).unwrap();
+        snapshot.set(VERSION, 0x1234).unwrap();
+        assert!(snapshot.set(REGSIZE as u16, 0).is_err());
+
+        let text = snapshot.to_string();
+        assert!(text.contains("0x1F = 0x0004  # BANDWIDTH\n"));
+        assert_eq!(text.parse::<RegisterSnapshot>().unwrap(), snapshot);
+        assert!(text.replace("version = 1", "version = 2").parse::<RegisterSnapshot>().is_err());
+
+        // BAUD and VERSION are never restored
+        assert_eq!(snapshot.restore_writes(&RestoreFilter::All), [(BANDWIDTH, 4), (ACCRANGE, 3)]);
+        assert_eq!(snapshot.restore_writes(&RestoreFilter::Except(vec![BANDWIDTH])), [(ACCRANGE, 3)]);
+        assert_eq!(snapshot.restore_writes(&RestoreFilter::Only(vec![BAUD, BANDWIDTH])), [(BANDWIDTH, 4)]);
+    }
+
+    #[test]
+    fn test_restore_writes_filtered_registers_under_unlock_then_saves() {
+        let transport = MockTransport::new(WitProtocol::Modbus)
+            .with(BAUD, 2)
+            .with(BANDWIDTH, 3)
+            .with(RRATE, 6);
+        let mut sensor = WitSensor::with_transport(transport);
+
+        let mut snapshot = sensor.dump_all().unwrap();
+        snapshot.set(BAUD, 6).unwrap();
+        snapshot.set(BANDWIDTH, 4).unwrap();
+        snapshot.set(ACCRANGE, 3).unwrap();
+        snapshot.set(RRATE, 9).unwrap();
+
+        let filter = RestoreFilter::Except(vec![RRATE]);
+        let writes = snapshot.restore_writes(&filter);
+        assert_eq!(sensor.restore(&snapshot, &filter).unwrap(), writes.len());
+
+        // Registers already holding the snapshot value are written too, but never BAUD or RRATE
+        let transport = sensor.transport();
+        let expected: Vec<_> = writes.iter().copied().chain([(SAVE, SAVE_PARAM)]).collect();
+        assert_eq!(transport.writes_after_unlock().unwrap(), expected);
+        assert!(!expected.iter().any(|&(register, _)| register == BAUD || register == RRATE));
+        assert_eq!((transport.saved[&BANDWIDTH], transport.saved[&ACCRANGE]), (4, 3));
+        assert_eq!((transport.saved[&BAUD], transport.saved[&RRATE]), (2, 6));
+
+        // Snapshots of another model are refused before anything is written
+        let count = sensor.transport().writes.len();
+        let other = RegisterSnapshot::new(SensorModel::Hwt905);
+        assert!(sensor.restore(&other, &RestoreFilter::All).is_err());
+        assert_eq!(sensor.transport().writes.len(), count);
+    }
+}
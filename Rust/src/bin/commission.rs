@@ -0,0 +1,130 @@
+use clap::Parser;
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    thread,
+    time::Duration,
+};
+use witmotion_modbus::{modbus::DEFAULT_SLAVE_ADDRESS, AddressMap, Commissioner, WitProtocol, WitSensor};
+
+/// Parse address argument that can be in hex (0x51) or decimal (81) format
+fn parse_address(s: &str) -> Result<u8, String> {
+    if s.starts_with("0x") || s.starts_with("0X") {
+        u8::from_str_radix(&s[2..], 16)
+            .map_err(|_| format!("Invalid hex address: {}", s))
+    } else {
+        s.parse::<u8>()
+            .map_err(|_| format!("Invalid decimal address: {}", s))
+    }
+}
+
+/// Command line arguments
+#[derive(Parser, Debug)]
+#[command(name = "commission")]
+#[command(about = "Give factory-default WitMotion Modbus sensors unique addresses, one at a time")]
+struct Args {
+    /// Serial device path (e.g., /dev/ttyUSB0)
+    #[arg(short, long)]
+    device: String,
+
+    /// Baud rate of the sensors
+    #[arg(short, long, default_value_t = 9600)]
+    baud_rate: u32,
+
+    /// First address to hand out
+    /// Accepts hex format (0x51) or decimal format (81)
+    #[arg(short = 'f', long, default_value = "0x51", value_parser = parse_address)]
+    first_address: u8,
+
+    /// File the name to address map is kept in, loaded first if it exists
+    #[arg(short, long, default_value = "sensors.map")]
+    map: PathBuf,
+
+    /// Use the HWT905 register layout (32-bit angles)
+    #[arg(long)]
+    hwt905: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    println!("WitMotion Sensor Commissioning");
+    println!("==============================");
+
+    let map = match fs::read_to_string(&args.map) {
+        Ok(text) => text.parse::<AddressMap>()?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => AddressMap::new(),
+        Err(e) => return Err(e.into()),
+    };
+    for (name, address) in map.iter() {
+        println!("Already commissioned: {} at 0x{:02X}", name, address);
+    }
+
+    let protocol = if args.hwt905 { WitProtocol::Modbus905x } else { WitProtocol::Modbus };
+    let mut sensor = WitSensor::with_protocol(&args.device, DEFAULT_SLAVE_ADDRESS, protocol)?;
+    sensor.transport_mut().serial_mut().set_baud_rate(args.baud_rate)?;
+    let mut commissioner = Commissioner::with_map(map, args.first_address);
+
+    let stdin = io::stdin();
+    loop {
+        println!();
+        print!("Connect the next sensor and enter its name (empty to finish): ");
+        io::stdout().flush()?;
+
+        let mut name = String::new();
+        if stdin.lock().read_line(&mut name)? == 0 {
+            break;
+        }
+        let name = name.trim();
+        if name.is_empty() {
+            break;
+        }
+
+        // Give the sensor a moment to power up
+        let mut detected = false;
+        for _ in 0..10 {
+            if commissioner.detect(&mut sensor)? {
+                detected = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(500));
+        }
+        if !detected {
+            eprintln!("No sensor answers at the factory address 0x50, check the wiring and try again");
+            continue;
+        }
+
+        match commissioner.commission(&mut sensor, name) {
+            Ok(address) => {
+                println!("{} now answers at 0x{:02X}", name, address);
+                fs::write(&args.map, commissioner.map().to_string())?;
+            }
+            Err(e) => eprintln!("Could not commission {}: {}", name, e),
+        }
+    }
+
+    println!();
+    println!("Commissioned {} sensor(s), map saved to {}", commissioner.map().len(), args.map.display());
+    print!("{}", commissioner.map());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_parsing() {
+        let args = Args::try_parse_from([
+            "commission",
+            "--device", "/dev/ttyUSB0",
+            "--first-address", "0x60",
+        ]).unwrap();
+
+        assert_eq!(args.device, "/dev/ttyUSB0");
+        assert_eq!(args.baud_rate, 9600);
+        assert_eq!(args.first_address, 0x60);
+        assert_eq!(args.map, PathBuf::from("sensors.map"));
+    }
+}
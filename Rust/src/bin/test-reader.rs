@@ -1,6 +1,9 @@
 use clap::Parser;
 use std::{thread, time::Duration};
-use witmotion_modbus::{WitSensor, DEFAULT_POLL_INTERVAL_MS};
+use witmotion_modbus::{
+    modbus::MAX_SLAVE_ADDRESS, DeviceProfile, SensorModel, WitProtocol, WitSensor,
+    DEFAULT_POLL_INTERVAL_MS, SUPPORTED_BAUD_RATES,
+};
 
 /// Parse address argument that can be in hex (0x50) or decimal (80) format
 fn parse_address(s: &str) -> Result<u8, String> {
@@ -13,6 +16,17 @@ fn parse_address(s: &str) -> Result<u8, String> {
     }
 }
 
+/// Parse protocol argument ("modbus", "modbus905x", "normal" or "jy61")
+fn parse_protocol(s: &str) -> Result<WitProtocol, String> {
+    match s.to_ascii_lowercase().as_str() {
+        "modbus" => Ok(WitProtocol::Modbus),
+        "modbus905x" => Ok(WitProtocol::Modbus905x),
+        "normal" => Ok(WitProtocol::Normal),
+        "jy61" => Ok(WitProtocol::Jy61),
+        _ => Err(format!("Invalid protocol: {} (expected modbus, modbus905x, normal or jy61)", s)),
+    }
+}
+
 /// Command line arguments
 #[derive(Parser, Debug)]
 #[command(name = "test-reader")]
@@ -27,6 +41,16 @@ struct Args {
     #[arg(short = 'a', long, default_value = "255", value_parser = parse_address)]
     address: u8,
 
+    /// Sensor protocol: modbus (RS485), modbus905x (HWT905 32-bit angles),
+    /// normal (TTL/RS232 0x55 stream) or jy61 (6-axis 0x55 stream)
+    #[arg(short = 'p', long, default_value = "modbus", value_parser = parse_protocol)]
+    protocol: WitProtocol,
+
+    /// Sensor model used to decode registers (wt901c485, hwt905, wt901, wt901bc or jy61)
+    /// Detected during auto-scan when not given
+    #[arg(short = 'm', long)]
+    model: Option<SensorModel>,
+
     /// Polling interval in milliseconds
     #[arg(short = 'i', long, default_value_t = DEFAULT_POLL_INTERVAL_MS)]
     interval: u64,
@@ -35,6 +59,11 @@ struct Args {
     #[arg(short = 'b', long)]
     baud_rate: Option<u32>,
 
+    /// List every Modbus sensor on the bus (addresses 1-247) and exit
+    /// Only the given baud rate is scanned when --baud-rate is set
+    #[arg(long)]
+    scan_bus: bool,
+
     /// Enable verbose output
     #[arg(short, long)]
     verbose: bool,
@@ -47,14 +76,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("==============================");
 
     // Create sensor instance
-    let mut sensor = WitSensor::new(
+    let mut sensor = WitSensor::with_protocol(
         &args.device,
         args.address,
+        args.protocol,
     )?;
 
-    // Initialize sensor
-    sensor.init()?;
-    println!("Sensor initialized");
+    if args.scan_bus {
+        let baud_rates = match args.baud_rate {
+            Some(baud) => vec![baud],
+            None => SUPPORTED_BAUD_RATES.to_vec(),
+        };
+        println!("Scanning bus addresses 1-{}...", MAX_SLAVE_ADDRESS);
+        let devices = sensor.scan_bus(&baud_rates, 1..=MAX_SLAVE_ADDRESS)?;
+        for device in &devices {
+            println!(
+                "  0x{:02X} at {} baud: {} ({:?}), firmware 0x{:04X}",
+                device.address, device.baud, device.model, device.protocol, device.version
+            );
+        }
+        println!("Found {} sensor(s)", devices.len());
+        return Ok(());
+    }
 
     // Auto-scan or use specified baud rate
     let baud_rate = match args.baud_rate {
@@ -64,11 +107,43 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         None => {
             println!("Auto-scanning for sensor...");
-            sensor.auto_scan()?
+            let result = sensor.auto_scan()?;
+            println!("Found sensor using {:?} protocol", result.protocol);
+            if let Some(address) = result.address {
+                println!("Sensor address: 0x{:02X}", address);
+            }
+            if let Some(model) = result.model {
+                println!("Sensor model: {}", model);
+            }
+            result.baud
         }
     };
 
+    if let Some(model) = args.model {
+        sensor.set_profile(DeviceProfile::for_model(model));
+    }
+
+    // Initialize sensor, which reads the measurement ranges used for scaling
+    match sensor.init() {
+        Ok(()) => println!("Sensor initialized ({}, {})", sensor.ranges().acc, sensor.ranges().gyro),
+        Err(e) => eprintln!(
+            "Could not read the measurement ranges, assuming {} and {}: {}",
+            sensor.ranges().acc,
+            sensor.ranges().gyro,
+            e
+        ),
+    }
+
     println!("Communication established at {} baud", baud_rate);
+    match sensor.device_info() {
+        Ok(info) => {
+            println!("Model: {}", info.model);
+            println!("Firmware version: {}", info.firmware_version);
+            println!("Serial number: {}", info.serial_number);
+            println!("Chip ID: 0x{:08X}", info.chip_id);
+        }
+        Err(e) => eprintln!("Could not read device information: {}", e),
+    }
     println!("Starting data acquisition...");
     println!();
 
@@ -98,10 +173,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     );
                 }
 
-                if data.has_magnetometer_update() {
+                if let (true, Some(mag)) = (data.has_magnetometer_update(), data.magnetometer) {
+                    println!("Magnetometer: X={}, Y={}, Z={}", mag[0], mag[1], mag[2]);
+                }
+
+                if let (true, Some(pressure), Some(height)) =
+                    (data.has_pressure_update(), data.pressure, data.height)
+                {
+                    println!("Pressure: {} Pa, Height: {:.2} m", pressure, height);
+                }
+
+                if let (true, Some(gps)) = (data.has_gps_update(), data.gps) {
                     println!(
-                        "Magnetometer: X={}, Y={}, Z={}",
-                        data.magnetometer[0], data.magnetometer[1], data.magnetometer[2]
+                        "GPS: Lon={:.6}°, Lat={:.6}°, Height={:.1} m, Yaw={:.2}°, Speed={:.3} km/h",
+                        gps.longitude, gps.latitude, gps.height, gps.yaw, gps.speed
                     );
                 }
 
@@ -116,7 +201,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     let mut regs: Vec<_> = sensor.get_all_registers().iter().collect();
                     regs.sort_by_key(|(addr, _)| *addr);
                     for (addr, value) in regs {
-                        println!("  0x{:04X}: {}", addr, value);
+                        let name = sensor.profile().resolve(*addr).map_or("?", |reg| reg.name());
+                        println!("  0x{:04X} {:<11}: {}", addr, name, value);
                     }
                     println!();
                 }
@@ -138,29 +224,36 @@ mod tests {
     #[test]
     fn test_args_parsing() {
         // Test basic argument parsing
-        let args = Args::try_parse_from(&[
+        let args = Args::try_parse_from([
             "test-reader",
             "--device", "/dev/ttyUSB0",
         ]).unwrap();
         
         assert_eq!(args.device, "/dev/ttyUSB0");
         assert_eq!(args.address, 255);
+        assert_eq!(args.protocol, WitProtocol::Modbus);
         assert_eq!(args.interval, DEFAULT_POLL_INTERVAL_MS);
     }
 
     #[test]
     fn test_args_with_options() {
-        let args = Args::try_parse_from(&[
+        let args = Args::try_parse_from([
             "test-reader",
             "--device", "/dev/ttyUSB0",
             "--address", "50",
+            "--protocol", "normal",
+            "--model", "wt901bc",
             "--interval", "1000",
             "--verbose",
+            "--scan-bus",
         ]).unwrap();
         
         assert_eq!(args.device, "/dev/ttyUSB0");
         assert_eq!(args.address, 50);
+        assert_eq!(args.protocol, WitProtocol::Normal);
+        assert_eq!(args.model, Some(SensorModel::Wt901Bc));
         assert_eq!(args.interval, 1000);
-        assert_eq!(args.verbose, true);
+        assert!(args.verbose);
+        assert!(args.scan_bus);
     }
 }
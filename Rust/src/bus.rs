@@ -0,0 +1,381 @@
+use crate::{
+    error::{WitError, WitResult},
+    modbus::MAX_SLAVE_ADDRESS,
+    profile::DeviceProfile,
+    registers::*,
+    sensor::{MeasurementRanges, SensorData, WitSensor},
+    serial::DEFAULT_READ_TIMEOUT,
+    transport::{SerialTransport, Transport},
+};
+use std::{
+    collections::HashMap,
+    mem, thread,
+    time::{Duration, Instant},
+};
+
+/// Time a sensor and USB adapter may take before the first byte of an answer
+pub const DEFAULT_RESPONSE_MARGIN: Duration = Duration::from_millis(20);
+
+/// Shortest pause between the end of an answer and the next request
+const MIN_TURNAROUND: Duration = Duration::from_millis(2);
+
+/// A measurement taken from one sensor on the bus
+#[derive(Debug, Clone)]
+pub struct BusReading {
+    /// Modbus slave address of the sensor
+    pub address: u8,
+    /// When the answer was received
+    pub timestamp: Instant,
+    /// Decoded data, None if the sensor did not answer in time
+    pub data: Option<SensorData>,
+}
+
+/// A sensor registered with the bus manager
+struct BusSlave {
+    address: u8,
+    profile: &'static DeviceProfile,
+    weight: u32,
+    /// Smooth weighted round-robin credit
+    credit: i64,
+    registers: HashMap<u16, i16>,
+    /// None until read from the sensor or declared
+    ranges: Option<MeasurementRanges>,
+    latest: Option<BusReading>,
+}
+
+/// Polls several Modbus sensors sharing one RS485 line
+///
+/// Each call to `poll` picks the next sensor with smooth weighted round-robin,
+/// so a sensor with weight 3 is polled three times as often as one with weight
+/// 1, evenly spread. Equal weights give plain round-robin. Requests are spaced
+/// by the turnaround time, since only one device may drive the line at a time.
+/// A sensor that answers with an error gives a reading without data, the
+/// others are still polled.
+pub struct BusManager<T: Transport = SerialTransport> {
+    sensor: WitSensor<T>,
+    slaves: Vec<BusSlave>,
+    turnaround: Duration,
+    response_margin: Duration,
+    last_activity: Instant,
+}
+
+impl<T: Transport> BusManager<T> {
+    /// Take over the port of a sensor to poll several addresses on it
+    pub fn new(mut sensor: WitSensor<T>) -> WitResult<Self> {
+        if !sensor.protocol().is_modbus() {
+            return Err(WitError::InvalidParameter(
+                "A bus manager needs sensors speaking Modbus".to_string(),
+            ));
+        }
+
+        // Answers are timed by the manager, so reads should not block for long
+        sensor.transport_mut().set_read_timeout(Duration::from_millis(1))?;
+        // Modbus RTU frames are separated by at least 3.5 characters of silence
+        let turnaround = sensor.transport().transmission_time(4).max(MIN_TURNAROUND);
+
+        Ok(Self {
+            sensor,
+            slaves: Vec::new(),
+            turnaround,
+            response_margin: DEFAULT_RESPONSE_MARGIN,
+            last_activity: Instant::now(),
+        })
+    }
+
+    /// Register a sensor, polled `weight` times per round
+    pub fn add_sensor(&mut self, address: u8, profile: &'static DeviceProfile, weight: u32) -> WitResult<()> {
+        if !(1..=MAX_SLAVE_ADDRESS).contains(&address) {
+            return Err(WitError::InvalidParameter(format!(
+                "Invalid slave address: {} (expected 1 to {})",
+                address, MAX_SLAVE_ADDRESS
+            )));
+        }
+        if weight == 0 {
+            return Err(WitError::InvalidParameter("Polling weight must be at least 1".to_string()));
+        }
+        if self.slaves.iter().any(|slave| slave.address == address) {
+            return Err(WitError::InvalidParameter(format!(
+                "Address 0x{:02X} is already registered",
+                address
+            )));
+        }
+
+        self.slaves.push(BusSlave {
+            address,
+            profile,
+            weight,
+            credit: 0,
+            registers: HashMap::new(),
+            ranges: None,
+            latest: None,
+        });
+        Ok(())
+    }
+
+    /// Stop polling a sensor
+    pub fn remove_sensor(&mut self, address: u8) -> bool {
+        let count = self.slaves.len();
+        self.slaves.retain(|slave| slave.address != address);
+        self.slaves.len() != count
+    }
+
+    /// Declare the measurement ranges of a sensor, used to scale its readings
+    /// Otherwise they are read from the sensor with its first answer
+    /// Returns false if the address is not registered
+    pub fn set_ranges(&mut self, address: u8, ranges: MeasurementRanges) -> bool {
+        match self.slaves.iter_mut().find(|slave| slave.address == address) {
+            Some(slave) => {
+                slave.ranges = Some(ranges);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the registered addresses in registration order
+    pub fn addresses(&self) -> Vec<u8> {
+        self.slaves.iter().map(|slave| slave.address).collect()
+    }
+
+    /// Set the pause between an answer and the next request
+    pub fn set_turnaround(&mut self, turnaround: Duration) {
+        self.turnaround = turnaround;
+    }
+
+    /// Set how long to wait for an answer beyond its transmission time
+    pub fn set_response_margin(&mut self, margin: Duration) {
+        self.response_margin = margin;
+    }
+
+    /// Get the latest reading of a sensor
+    pub fn latest(&self, address: u8) -> Option<&BusReading> {
+        self.slaves
+            .iter()
+            .find(|slave| slave.address == address)
+            .and_then(|slave| slave.latest.as_ref())
+    }
+
+    /// Get the register values last received from a sensor
+    pub fn registers(&self, address: u8) -> Option<&HashMap<u16, i16>> {
+        self.slaves
+            .iter()
+            .find(|slave| slave.address == address)
+            .map(|slave| &slave.registers)
+    }
+
+    /// Poll the next sensor in the schedule
+    /// A sensor that does not answer, or answers with an error, gives a reading without data
+    pub fn poll(&mut self) -> WitResult<BusReading> {
+        let index = next_slave(&mut self.slaves)
+            .ok_or_else(|| WitError::InvalidParameter("No sensors registered on the bus".to_string()))?;
+        let slave = &mut self.slaves[index];
+
+        // Talk to this slave with its own register cache
+        self.sensor.transport_mut().set_modbus_address(slave.address)?;
+        self.sensor.set_profile(slave.profile);
+        self.sensor.set_ranges(slave.ranges.unwrap_or_default());
+        // Until the ranges are known, read GYRORANGE and ACCRANGE along with the data
+        let start = if slave.ranges.is_some() { AX } else { GYRORANGE };
+        mem::swap(self.sensor.registers_mut(), &mut slave.registers);
+
+        let result = Self::exchange(
+            &mut self.sensor,
+            start,
+            slave.profile.read_count() + (AX - start),
+            self.last_activity + self.turnaround,
+            self.response_margin,
+        );
+        self.last_activity = Instant::now();
+
+        let slave = &mut self.slaves[index];
+        mem::swap(self.sensor.registers_mut(), &mut slave.registers);
+
+        let data = match result {
+            Ok(data) => data,
+            // The port itself failed, no other sensor can be reached either
+            Err(WitError::Io(e)) => return Err(WitError::Io(e)),
+            // A bad frame or exception from one sensor must not stop the others
+            Err(_) => None,
+        };
+        let reading = BusReading {
+            address: slave.address,
+            timestamp: self.last_activity,
+            data,
+        };
+        if reading.data.is_some() {
+            // Keep the ranges the answer was scaled with
+            slave.ranges = Some(self.sensor.ranges());
+            slave.latest = Some(reading.clone());
+        }
+        Ok(reading)
+    }
+
+    /// Poll every sensor once per unit of weight
+    pub fn poll_round(&mut self) -> WitResult<Vec<BusReading>> {
+        let total: u32 = self.slaves.iter().map(|slave| slave.weight).sum();
+        (0..total).map(|_| self.poll()).collect()
+    }
+
+    /// Request the measurement block and wait for the answer
+    fn exchange(
+        sensor: &mut WitSensor<T>,
+        start: u16,
+        count: u16,
+        not_before: Instant,
+        margin: Duration,
+    ) -> WitResult<Option<SensorData>> {
+        // Wait out the turnaround so the last speaker has released the line
+        let now = Instant::now();
+        if not_before > now {
+            thread::sleep(not_before - now);
+        }
+
+        sensor.transport_mut().clear_input()?;
+        sensor.read_registers(start, count)?;
+
+        // addr + func + len + data + 2*CRC
+        let deadline = Instant::now() + sensor.transport().transmission_time(count as usize * 2 + 5) + margin;
+        while Instant::now() < deadline {
+            if let Some(data) = sensor.process_next_block()? {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Give the port back as a single sensor interface
+    pub fn into_sensor(mut self) -> WitResult<WitSensor<T>> {
+        self.sensor.transport_mut().set_read_timeout(DEFAULT_READ_TIMEOUT)?;
+        Ok(self.sensor)
+    }
+}
+
+/// Pick the next slave with smooth weighted round-robin
+fn next_slave(slaves: &mut [BusSlave]) -> Option<usize> {
+    let total: i64 = slaves.iter().map(|slave| slave.weight as i64).sum();
+    for slave in slaves.iter_mut() {
+        slave.credit += slave.weight as i64;
+    }
+
+    // Ties go to the slave registered first
+    let (index, slave) = slaves
+        .iter_mut()
+        .enumerate()
+        .max_by_key(|(i, slave)| (slave.credit, -(*i as i64)))?;
+    slave.credit -= total;
+    Some(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockTransport, protocol::WitProtocol, settings::AccRange};
+
+    /// A sensor at 0x50 and one at 0x51 on the bus, polled with a short answer margin
+    fn two_sensors() -> BusManager<MockTransport> {
+        let mut transport = MockTransport::new(WitProtocol::Modbus)
+            .with(IICADDR, 0x50)
+            .with(ACCRANGE, AccRange::G4.into())
+            .with(AX, 2048);
+        transport.address = Some(0x50);
+        transport.neighbours.push(0x51);
+
+        let mut bus = BusManager::new(WitSensor::with_transport(transport)).unwrap();
+        bus.set_response_margin(Duration::from_millis(5));
+        bus
+    }
+
+    #[test]
+    fn test_weighted_schedule() {
+        let mut slaves: Vec<BusSlave> = [(0x51, 3), (0x52, 1), (0x53, 1)]
+            .into_iter()
+            .map(|(address, weight)| BusSlave {
+                address,
+                profile: &DeviceProfile::WT901C485,
+                weight,
+                credit: 0,
+                registers: HashMap::new(),
+                ranges: None,
+                latest: None,
+            })
+            .collect();
+
+        let order: Vec<u8> = (0..10)
+            .map(|_| {
+                let index = next_slave(&mut slaves).unwrap();
+                slaves[index].address
+            })
+            .collect();
+        assert_eq!(order, [0x51, 0x52, 0x51, 0x53, 0x51, 0x51, 0x52, 0x51, 0x53, 0x51]);
+        assert_eq!(next_slave(&mut []), None);
+    }
+
+    #[test]
+    fn test_round_reads_each_sensor_into_its_own_cache() {
+        let mut bus = two_sensors();
+        for address in [0x50, 0x51, 0x53] {
+            bus.add_sensor(address, &DeviceProfile::WT901C485, 1).unwrap();
+        }
+        let turnaround = Duration::from_millis(10);
+        bus.set_turnaround(turnaround);
+
+        let readings = bus.poll_round().unwrap();
+        let addresses: Vec<u8> = readings.iter().map(|reading| reading.address).collect();
+        assert_eq!(addresses, [0x50, 0x51, 0x53]);
+        // Scaled with the ±4 g range read from the sensor
+        assert_eq!(readings[0].data.as_ref().unwrap().accelerometer[0], 0.25);
+        assert_eq!(readings[1].data.as_ref().unwrap().accelerometer[0], 0.0);
+        // 0x53 is silent
+        assert!(readings[2].data.is_none());
+        assert!(bus.latest(0x53).is_none());
+
+        // Requests are spaced by the turnaround
+        for pair in readings.windows(2) {
+            assert!(pair[1].timestamp - pair[0].timestamp >= turnaround);
+        }
+
+        // Each sensor keeps the registers it sent
+        assert_eq!(bus.registers(0x50).unwrap()[&AX], 2048);
+        assert_eq!(bus.registers(0x51).unwrap()[&AX], 0);
+        assert!(bus.registers(0x53).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ranges_are_read_once_or_declared() {
+        let mut bus = two_sensors();
+        bus.add_sensor(0x50, &DeviceProfile::WT901C485, 1).unwrap();
+
+        let first = bus.poll().unwrap();
+        assert_eq!(first.data.unwrap().accelerometer[0], 0.25);
+        assert!(bus.registers(0x50).unwrap().contains_key(&ACCRANGE));
+
+        // Later polls only read the data and keep the ranges
+        bus.sensor.transport_mut().registers.insert(ACCRANGE, AccRange::G16.into());
+        assert_eq!(bus.poll().unwrap().data.unwrap().accelerometer[0], 0.25);
+
+        // Declared ranges are used as they are
+        let mut bus = two_sensors();
+        bus.add_sensor(0x50, &DeviceProfile::WT901C485, 1).unwrap();
+        bus.set_ranges(0x50, MeasurementRanges::default());
+        assert_eq!(bus.poll().unwrap().data.unwrap().accelerometer[0], 1.0);
+        assert!(!bus.registers(0x50).unwrap().contains_key(&ACCRANGE));
+    }
+
+    #[test]
+    fn test_sensor_errors_do_not_stop_the_round() {
+        let mut bus = two_sensors();
+        bus.add_sensor(0x50, &DeviceProfile::WT901C485, 1).unwrap();
+        bus.add_sensor(0x51, &DeviceProfile::WT901C485, 1).unwrap();
+        bus.poll_round().unwrap();
+
+        // 0x50 now answers with exceptions
+        bus.sensor.transport_mut().reads_before_exception = Some(0);
+        let readings = bus.poll_round().unwrap();
+        assert!(readings[0].data.is_none());
+        assert!(readings[1].data.is_some());
+
+        // The last good reading is kept
+        assert!(bus.latest(0x50).unwrap().data.is_some());
+        assert!(bus.latest(0x50).unwrap().timestamp < readings[0].timestamp);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::{
+    error::{WitError, WitResult},
+    normal::{encode_command, packet_register, PACKET_HEADER},
+    protocol::{RegisterBlock, WitProtocol},
+    registers::*,
+    transport::{invalid_range, Transport},
+};
+use std::collections::VecDeque;
+
+/// Number of registers returned by a single register read over CAN
+pub const REGISTERS_PER_CAN_READ: u16 = 3;
+
+/// Length of the data frames sent by WitMotion CAN sensors
+pub const CAN_DATA_LENGTH: usize = 8;
+
+/// Maximum payload of a classic CAN frame
+pub const CAN_MAX_DATA_LENGTH: usize = 8;
+
+/// CAN identifier that makes the protocol accept frames from any sensor
+pub const CAN_ID_ANY: u32 = 0xFF;
+
+/// A classic CAN data frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanFrame {
+    /// Frame identifier (11-bit standard or 29-bit extended)
+    pub id: u32,
+    /// Whether the identifier is a 29-bit extended one
+    pub extended: bool,
+    /// Frame payload (up to 8 bytes)
+    pub data: Vec<u8>,
+}
+
+impl CanFrame {
+    /// Create a standard frame, checking the payload length
+    pub fn new(id: u32, data: &[u8]) -> WitResult<Self> {
+        if data.len() > CAN_MAX_DATA_LENGTH {
+            return Err(WitError::InvalidParameter(format!(
+                "CAN payload too long: {} bytes",
+                data.len()
+            )));
+        }
+
+        Ok(Self {
+            id,
+            extended: false,
+            data: data.to_vec(),
+        })
+    }
+}
+
+/// Raw CAN interface that frames are exchanged over
+pub trait CanBus {
+    /// Send a frame
+    fn send(&mut self, frame: &CanFrame) -> WitResult<()>;
+
+    /// Receive the next frame, or None if nothing arrived before the read timeout
+    fn receive(&mut self) -> WitResult<Option<CanFrame>>;
+}
+
+/// WitMotion CAN protocol handler
+///
+/// Commands are the `0xFF 0xAA reg lo hi` sequences of the normal protocol sent
+/// to the sensor's CAN ID. The sensor answers with 8-byte `0x55 0x5X` frames
+/// carrying three registers each, or two registers of a 32-bit angle on WT905x.
+pub struct CanProtocol {
+    can_id: u32,
+    high_precision: bool,
+    pending_reads: VecDeque<u16>,
+}
+
+impl CanProtocol {
+    /// Create a new CAN protocol handler for the sensor with the given CAN ID
+    /// `high_precision` selects the WT905x 32-bit angle frames
+    pub fn new(can_id: u32, high_precision: bool) -> Self {
+        Self {
+            can_id,
+            high_precision,
+            pending_reads: VecDeque::new(),
+        }
+    }
+
+    /// Get the CAN ID of the sensor
+    pub fn can_id(&self) -> u32 {
+        self.can_id
+    }
+
+    /// Generate a register read request
+    /// The sensor answers with a 0x5F frame holding three registers from `start_register`
+    pub fn generate_read_request(&mut self, start_register: u16) -> CanFrame {
+        self.pending_reads.push_back(start_register);
+        self.command_frame(READADDR as u8, start_register)
+    }
+
+    /// Generate a register write request
+    pub fn generate_write_request(&self, register: u16, value: u16) -> CanFrame {
+        self.command_frame(register as u8, value)
+    }
+
+    /// Process a received frame and return parsed register data if it holds any
+    pub fn process_frame(&mut self, frame: &CanFrame) -> Option<RegisterBlock> {
+        if self.can_id != CAN_ID_ANY && frame.id != self.can_id {
+            return None;
+        }
+
+        let data = &frame.data;
+        if data.len() < CAN_DATA_LENGTH || data[0] != PACKET_HEADER {
+            return None;
+        }
+
+        let value = |offset: usize| i16::from_le_bytes([data[offset], data[offset + 1]]);
+
+        if self.high_precision && data[1] == WIT_ANGLE {
+            // One frame per axis: 0x55 0x53 axis 0x00 low high
+            let low_register = match data[2] {
+                0x01 => LROLL,
+                0x02 => LPITCH,
+                0x03 => LYAW,
+                _ => return None,
+            };
+            return Some((low_register, vec![value(4), value(6)]));
+        }
+
+        let start_register = match data[1] {
+            WIT_REGVALUE => self.pending_reads.pop_front()?,
+            packet_type => packet_register(packet_type)?,
+        };
+
+        Some((start_register, vec![value(2), value(4), value(6)]))
+    }
+
+    /// Forget pending register reads
+    pub fn clear_pending(&mut self) {
+        self.pending_reads.clear();
+    }
+
+    fn command_frame(&self, register: u8, value: u16) -> CanFrame {
+        CanFrame {
+            id: self.can_id,
+            extended: false,
+            data: encode_command(register, value),
+        }
+    }
+}
+
+/// Transport speaking the WitMotion CAN protocol over any CAN interface
+pub struct CanTransport<B: CanBus> {
+    bus: B,
+    can: CanProtocol,
+}
+
+impl<B: CanBus> CanTransport<B> {
+    /// Create a CAN transport talking to the sensor with the given CAN ID
+    /// `high_precision` selects the WT905x 32-bit angle frames
+    pub fn new(bus: B, can_id: u32, high_precision: bool) -> Self {
+        Self {
+            bus,
+            can: CanProtocol::new(can_id, high_precision),
+        }
+    }
+
+    /// Get the underlying CAN interface
+    pub fn bus(&self) -> &B {
+        &self.bus
+    }
+
+    /// Get the underlying CAN interface mutably
+    pub fn bus_mut(&mut self) -> &mut B {
+        &mut self.bus
+    }
+}
+
+impl<B: CanBus> Transport for CanTransport<B> {
+    fn protocol(&self) -> WitProtocol {
+        if self.can.high_precision {
+            WitProtocol::Can905x
+        } else {
+            WitProtocol::Can
+        }
+    }
+
+    fn read_registers(&mut self, start_register: u16, count: u16) -> WitResult<()> {
+        // Check the whole range first so nothing is sent for an invalid one
+        if start_register.checked_add(count.saturating_sub(1)).is_none() {
+            return Err(invalid_range(start_register, count));
+        }
+
+        // Each request returns three registers, so larger reads need several
+        for offset in (0..count).step_by(REGISTERS_PER_CAN_READ as usize) {
+            let frame = self.can.generate_read_request(start_register + offset);
+            self.bus.send(&frame)?;
+        }
+        Ok(())
+    }
+
+    fn registers_per_request(&self) -> u16 {
+        REGISTERS_PER_CAN_READ
+    }
+
+    fn request_overhead(&self) -> usize {
+        // 5-byte command frame, answered by a frame holding three registers
+        5 + CAN_DATA_LENGTH - 2 * REGISTERS_PER_CAN_READ as usize
+    }
+
+    fn write_register(&mut self, register: u16, value: u16) -> WitResult<()> {
+        let frame = self.can.generate_write_request(register, value);
+        self.bus.send(&frame)
+    }
+
+    fn receive(&mut self) -> WitResult<Option<RegisterBlock>> {
+        while let Some(frame) = self.bus.receive()? {
+            if let Some(block) = self.can.process_frame(&frame) {
+                return Ok(Some(block));
+            }
+        }
+        Ok(None)
+    }
+
+    fn clear_input(&mut self) -> WitResult<()> {
+        while self.bus.receive()?.is_some() {}
+        self.can.clear_pending();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_frame(id: u32, bytes: [u8; 8]) -> CanFrame {
+        CanFrame::new(id, &bytes).unwrap()
+    }
+
+    #[test]
+    fn test_command_frames() {
+        let mut can = CanProtocol::new(0x50, false);
+
+        let read = can.generate_read_request(AX);
+        assert_eq!(read.id, 0x50);
+        assert_eq!(read.data, vec![0xFF, 0xAA, 0x27, 0x34, 0x00]);
+
+        let write = can.generate_write_request(KEY, KEY_UNLOCK);
+        assert_eq!(write.data, vec![0xFF, 0xAA, 0x69, 0x88, 0xB5]);
+    }
+
+    #[test]
+    fn test_data_frames() {
+        let mut can = CanProtocol::new(0x50, false);
+
+        let gyro = data_frame(0x50, [0x55, 0x52, 0x01, 0x00, 0xFF, 0xFF, 0x00, 0x80]);
+        assert_eq!(can.process_frame(&gyro), Some((GX, vec![1, -1, i16::MIN])));
+
+        // Frames from other sensors are ignored
+        let other = data_frame(0x51, [0x55, 0x51, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00]);
+        assert_eq!(can.process_frame(&other), None);
+
+        let _ = can.generate_read_request(BANDWIDTH);
+        let reply = data_frame(0x50, [0x55, 0x5F, 0x04, 0x00, 0x03, 0x00, 0x03, 0x00]);
+        assert_eq!(can.process_frame(&reply), Some((BANDWIDTH, vec![4, 3, 3])));
+    }
+
+    /// Bus that records what was sent and never answers
+    #[derive(Default)]
+    struct RecordingBus {
+        sent: Vec<CanFrame>,
+    }
+
+    impl CanBus for RecordingBus {
+        fn send(&mut self, frame: &CanFrame) -> WitResult<()> {
+            self.sent.push(frame.clone());
+            Ok(())
+        }
+
+        fn receive(&mut self) -> WitResult<Option<CanFrame>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn test_reads_are_split_and_range_checked() {
+        let mut transport = CanTransport::new(RecordingBus::default(), 0x50, false);
+        transport.read_registers(AX, 7).unwrap();
+        let starts: Vec<u8> = transport.bus().sent.iter().map(|frame| frame.data[3]).collect();
+        assert_eq!(starts, [AX as u8, AX as u8 + 3, AX as u8 + 6]);
+
+        // Running past 0xFFFF is refused before anything is sent
+        assert!(transport.read_registers(0xFFFE, 3).is_err());
+        assert!(transport.read_registers(0xFFFE, 2).is_ok());
+        assert_eq!(transport.bus().sent.len(), 4);
+    }
+
+    #[test]
+    fn test_high_precision_angle_frames() {
+        let mut can = CanProtocol::new(0x50, true);
+
+        // 170000 millidegrees = 0x00029810
+        let yaw = data_frame(0x50, [0x55, 0x53, 0x03, 0x00, 0x10, 0x98, 0x02, 0x00]);
+        assert_eq!(can.process_frame(&yaw), Some((LYAW, vec![0x9810u16 as i16, 2])));
+
+        // Other packets keep the standard layout
+        let acc = data_frame(0x50, [0x55, 0x51, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00]);
+        assert_eq!(can.process_frame(&acc), Some((AX, vec![1, 2, 3])));
+    }
+}
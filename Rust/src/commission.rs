@@ -0,0 +1,232 @@
+use crate::{
+    error::{WitError, WitResult},
+    modbus::{DEFAULT_SLAVE_ADDRESS, MAX_SLAVE_ADDRESS},
+    registers::*,
+    sensor::WitSensor,
+    transport::Transport,
+};
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// Names given to commissioned sensors and the addresses they were assigned
+///
+/// The text form has one `name = 0x51` line per sensor, so a map can be saved
+/// between sessions and commissioning resumed where it stopped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressMap {
+    entries: BTreeMap<String, u8>,
+}
+
+impl AddressMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the address of a sensor
+    pub fn insert(&mut self, name: &str, address: u8) -> WitResult<()> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(['=', '\n', '#']) {
+            return Err(WitError::InvalidParameter(format!("Invalid sensor name: {:?}", name)));
+        }
+        if self.entries.contains_key(name) {
+            return Err(WitError::InvalidParameter(format!("Sensor name already used: {}", name)));
+        }
+        if let Some(other) = self.name_of(address) {
+            return Err(WitError::InvalidParameter(format!(
+                "Address 0x{:02X} is already assigned to {}",
+                address, other
+            )));
+        }
+
+        self.entries.insert(name.to_string(), address);
+        Ok(())
+    }
+
+    /// Get the address assigned to a sensor
+    pub fn get(&self, name: &str) -> Option<u8> {
+        self.entries.get(name).copied()
+    }
+
+    /// Get the name of the sensor at an address
+    pub fn name_of(&self, address: u8) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(_, &a)| a == address)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Iterate over the sensors sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u8)> {
+        self.entries.iter().map(|(name, &address)| (name.as_str(), address))
+    }
+
+    /// Number of sensors in the map
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether no sensor has been recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl fmt::Display for AddressMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, address) in self.iter() {
+            writeln!(f, "{} = 0x{:02X}", name, address)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AddressMap {
+    type Err = WitError;
+
+    fn from_str(s: &str) -> WitResult<Self> {
+        let mut map = AddressMap::new();
+
+        for (number, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || WitError::InvalidParameter(format!("Invalid address map line {}: {}", number + 1, line));
+            let (name, address) = line.split_once('=').ok_or_else(invalid)?;
+            let address = address.trim();
+            let address = match address.strip_prefix("0x").or_else(|| address.strip_prefix("0X")) {
+                Some(hex) => u8::from_str_radix(hex, 16),
+                None => address.parse(),
+            }
+            .map_err(|_| invalid())?;
+            map.insert(name, address)?;
+        }
+
+        Ok(map)
+    }
+}
+
+/// Gives factory-default sensors unique Modbus addresses, one at a time
+///
+/// The operator adds sensors to the bus one by one. Each new sensor answers at
+/// the factory address (0x50) and is moved to the next address that is neither
+/// recorded in the map nor answering on the bus, then recorded under its name.
+pub struct Commissioner {
+    factory_address: u8,
+    next_address: u8,
+    map: AddressMap,
+}
+
+impl Commissioner {
+    /// Start assigning addresses from `first_address`
+    pub fn new(first_address: u8) -> Self {
+        Self::with_map(AddressMap::new(), first_address)
+    }
+
+    /// Continue commissioning with sensors that already have addresses
+    pub fn with_map(map: AddressMap, first_address: u8) -> Self {
+        Self {
+            factory_address: DEFAULT_SLAVE_ADDRESS,
+            next_address: first_address,
+            map,
+        }
+    }
+
+    /// Get the sensors commissioned so far
+    pub fn map(&self) -> &AddressMap {
+        &self.map
+    }
+
+    /// Check whether a sensor answers at the factory address
+    pub fn detect<T: Transport>(&self, sensor: &mut WitSensor<T>) -> WitResult<bool> {
+        sensor.transport_mut().set_modbus_address(self.factory_address)?;
+        sensor.transport_mut().clear_input()?;
+
+        match sensor.query_register(IICADDR) {
+            Ok(_) => Ok(true),
+            Err(WitError::Timeout) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Move the sensor at the factory address to the next free address
+    /// Returns the address the sensor was given and saved
+    pub fn commission<T: Transport>(&mut self, sensor: &mut WitSensor<T>, name: &str) -> WitResult<u8> {
+        if self.map.get(name.trim()).is_some() {
+            return Err(WitError::InvalidParameter(format!("Sensor name already used: {}", name.trim())));
+        }
+        if !self.detect(sensor)? {
+            return Err(WitError::SensorNotFound);
+        }
+
+        loop {
+            let address = self.next_free_address()?;
+            self.next_address = address.saturating_add(1);
+
+            match sensor.change_address(address) {
+                Ok(()) => {
+                    self.map.insert(name, address)?;
+                    return Ok(address);
+                }
+                // Taken by a sensor that is not in the map, try the next one
+                Err(WitError::AddressInUse(_)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// First address from `next_address` on that is neither mapped nor the factory default
+    fn next_free_address(&self) -> WitResult<u8> {
+        (self.next_address.max(1)..=MAX_SLAVE_ADDRESS)
+            .find(|&address| address != self.factory_address && self.map.name_of(address).is_none())
+            .ok_or_else(|| WitError::InvalidParameter("No free Modbus address left".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockTransport, protocol::WitProtocol};
+
+    #[test]
+    fn test_address_map_round_trip() {
+        let mut map = AddressMap::new();
+        map.insert("boom", 0x51).unwrap();
+        map.insert(" mast ", 0x52).unwrap();
+        assert!(map.insert("keel", 0x51).is_err());
+        assert!(map.insert("boom", 0x53).is_err());
+
+        let text = map.to_string();
+        assert_eq!(text, "boom = 0x51\nmast = 0x52\n");
+        assert_eq!(text.parse::<AddressMap>().unwrap(), map);
+        assert_eq!("# bus 1\nkeel = 83\n".parse::<AddressMap>().unwrap().get("keel"), Some(83));
+    }
+
+    #[test]
+    fn test_commissioning_skips_taken_addresses() {
+        // A sensor at the factory address, an unmapped one at 0x51 and "boom" at 0x52
+        let mut transport = MockTransport::new(WitProtocol::Modbus).with(IICADDR, 0x50);
+        transport.address = Some(0x50);
+        transport.neighbours.extend([0x51, 0x52]);
+        let mut sensor = WitSensor::with_transport(transport);
+
+        let mut commissioner = Commissioner::with_map("boom = 0x52".parse().unwrap(), 0x51);
+        assert!(commissioner.detect(&mut sensor).unwrap());
+        assert_eq!(commissioner.commission(&mut sensor, "mast").unwrap(), 0x53);
+        assert_eq!(sensor.transport().saved[&IICADDR], 0x53);
+
+        // Nothing left at the factory address
+        assert!(!commissioner.detect(&mut sensor).unwrap());
+        assert!(matches!(commissioner.commission(&mut sensor, "keel"), Err(WitError::SensorNotFound)));
+
+        // The operator adds the next sensor
+        let transport = sensor.transport_mut();
+        transport.neighbours.push(0x53);
+        transport.registers.insert(IICADDR, 0x50);
+        assert!(commissioner.commission(&mut sensor, "mast").is_err());
+        assert_eq!(commissioner.commission(&mut sensor, "keel").unwrap(), 0x54);
+
+        assert_eq!(commissioner.map().to_string(), "boom = 0x52\nkeel = 0x54\nmast = 0x53\n");
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{
+    backup::RegisterSnapshot,
+    error::{WitError, WitResult},
+    registers::*,
+    sensor::WitSensor,
+    settings::*,
+    transaction::ConfigTransaction,
+    transport::Transport,
+};
+use std::fmt;
+
+/// The configuration of a sensor that decides how it measures and reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceConfig {
+    pub output_content: OutputContent,
+    pub output_rate: OutputRate,
+    pub bandwidth: Bandwidth,
+    pub acc_range: AccRange,
+    pub gyro_range: GyroRange,
+    pub orientation: Orientation,
+    pub algorithm: AlgorithmAxes,
+    /// Raw X, Y and Z offsets (AXOFFSET..AZOFFSET)
+    pub acc_offset: [i16; 3],
+    /// Raw X, Y and Z offsets (GXOFFSET..GZOFFSET)
+    pub gyro_offset: [i16; 3],
+    /// Raw X, Y and Z offsets (HXOFFSET..HZOFFSET)
+    pub mag_offset: [i16; 3],
+    /// Attitude filter coefficient (FILTK)
+    pub filter_k: u16,
+    /// Accelerometer filter (ACCFILT)
+    pub acc_filter: u16,
+    /// Gyroscope filter (GYROFILT)
+    pub gyro_filter: u16,
+    /// Magnetometer filter (MAGFILT)
+    pub mag_filter: u16,
+}
+
+/// A setting that differs between two configurations
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDifference {
+    pub register: u16,
+    /// Human readable name of the setting
+    pub setting: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+impl fmt::Display for ConfigDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.setting, self.old, self.new)
+    }
+}
+
+impl ConfigDifference {
+    /// Describe a register changing from `old` to `new`
+    pub(crate) fn new(register: u16, old: u16, new: u16) -> Self {
+        let (setting, old) = describe(register, old);
+        let (_, new) = describe(register, new);
+        Self { register, setting, old, new }
+    }
+}
+
+impl DeviceConfig {
+    /// Registers a configuration is made of, in address order
+    pub const REGISTERS: &'static [u16] = &[
+        RSW, RRATE, AXOFFSET, AYOFFSET, AZOFFSET, GXOFFSET, GYOFFSET, GZOFFSET, HXOFFSET, HYOFFSET,
+        HZOFFSET, BANDWIDTH, GYRORANGE, ACCRANGE, ORIENT, AXIS6, FILTK, ACCFILT, GYROFILT, MAGFILT,
+    ];
+
+    /// Take the configuration out of a register dump
+    pub fn from_snapshot(snapshot: &RegisterSnapshot) -> WitResult<Self> {
+        let get = |register: u16| {
+            snapshot.get(register).ok_or_else(|| {
+                WitError::InvalidParameter(format!("Snapshot is missing {}", register_name(register)))
+            })
+        };
+        let offsets = |first: u16| -> WitResult<[i16; 3]> {
+            Ok([get(first)? as i16, get(first + 1)? as i16, get(first + 2)? as i16])
+        };
+
+        Ok(Self {
+            output_content: OutputContent::from_register(get(RSW)?),
+            output_rate: OutputRate::try_from(get(RRATE)?)?,
+            bandwidth: Bandwidth::try_from(get(BANDWIDTH)?)?,
+            acc_range: AccRange::try_from(get(ACCRANGE)?)?,
+            gyro_range: GyroRange::try_from(get(GYRORANGE)?)?,
+            orientation: Orientation::try_from(get(ORIENT)?)?,
+            algorithm: AlgorithmAxes::try_from(get(AXIS6)?)?,
+            acc_offset: offsets(AXOFFSET)?,
+            gyro_offset: offsets(GXOFFSET)?,
+            mag_offset: offsets(HXOFFSET)?,
+            filter_k: get(FILTK)?,
+            acc_filter: get(ACCFILT)?,
+            gyro_filter: get(GYROFILT)?,
+            mag_filter: get(MAGFILT)?,
+        })
+    }
+
+    /// Register values of the configuration as (register, value) pairs, in address order
+    pub fn register_values(&self) -> Vec<(u16, u16)> {
+        let [ax, ay, az] = self.acc_offset;
+        let [gx, gy, gz] = self.gyro_offset;
+        let [hx, hy, hz] = self.mag_offset;
+        let values = [
+            self.output_content.register_value(),
+            self.output_rate.into(),
+            ax as u16,
+            ay as u16,
+            az as u16,
+            gx as u16,
+            gy as u16,
+            gz as u16,
+            hx as u16,
+            hy as u16,
+            hz as u16,
+            self.bandwidth.into(),
+            self.gyro_range.into(),
+            self.acc_range.into(),
+            self.orientation.into(),
+            self.algorithm.into(),
+            self.filter_k,
+            self.acc_filter,
+            self.gyro_filter,
+            self.mag_filter,
+        ];
+        Self::REGISTERS.iter().copied().zip(values).collect()
+    }
+
+    /// Settings that change when going from this configuration to `other`
+    pub fn diff(&self, other: &DeviceConfig) -> Vec<ConfigDifference> {
+        self.register_values()
+            .into_iter()
+            .zip(other.register_values())
+            .filter(|((_, old), (_, new))| old != new)
+            .map(|((register, old), (_, new))| ConfigDifference::new(register, old, new))
+            .collect()
+    }
+}
+
+impl<T: Transport> WitSensor<T> {
+    /// Read the configuration of the sensor
+    pub fn read_config(&mut self) -> WitResult<DeviceConfig> {
+        let values = self.read_register_values(DeviceConfig::REGISTERS)?;
+
+        let mut snapshot = RegisterSnapshot::new(self.profile().model);
+        for (&register, value) in DeviceConfig::REGISTERS.iter().zip(values) {
+            snapshot.set(register, value)?;
+        }
+        DeviceConfig::from_snapshot(&snapshot)
+    }
+
+    /// Copy the configuration of this sensor to another one
+    ///
+    /// Both sensors must be of the same model. Only the differing registers are
+    /// written, as one verified transaction. Returns what was changed on the other sensor.
+    ///
+    /// The accelerometer, gyroscope and magnetometer offsets are calibration of
+    /// one unit, so they are only copied with `copy_offsets`. Otherwise the other
+    /// sensor keeps its own calibration.
+    pub fn clone_to<U: Transport>(
+        &mut self,
+        other: &mut WitSensor<U>,
+        copy_offsets: bool,
+    ) -> WitResult<Vec<ConfigDifference>> {
+        if self.profile() != other.profile() {
+            return Err(WitError::InvalidParameter(format!(
+                "Configuration of a {} cannot be cloned to a {}",
+                self.profile().model,
+                other.profile().model
+            )));
+        }
+
+        let source = self.read_config()?;
+        let mut differences = other.read_config()?.diff(&source);
+        if !copy_offsets {
+            differences.retain(|difference| !is_offset(difference.register));
+        }
+
+        let mut transaction = ConfigTransaction::new();
+        for (register, value) in source.register_values() {
+            if differences.iter().any(|difference| difference.register == register) {
+                transaction.write(register, value);
+            }
+        }
+        transaction.commit(other)?;
+        Ok(differences)
+    }
+}
+
+/// Human readable name and value of a configuration register
+pub(crate) fn describe(register: u16, value: u16) -> (&'static str, String) {
+    fn typed<V: RegisterValue + fmt::Display>(value: u16) -> String {
+        V::try_from(value).map_or_else(|_| format!("invalid ({})", value), |v| v.to_string())
+    }
+
+    match register {
+        RSW => ("output content", content_text(OutputContent::from_register(value))),
+        RRATE => ("output rate", typed::<OutputRate>(value)),
+        BAUD => ("baud rate", typed::<BaudRate>(value)),
+        AXOFFSET => ("accelerometer X offset", (value as i16).to_string()),
+        AYOFFSET => ("accelerometer Y offset", (value as i16).to_string()),
+        AZOFFSET => ("accelerometer Z offset", (value as i16).to_string()),
+        GXOFFSET => ("gyroscope X offset", (value as i16).to_string()),
+        GYOFFSET => ("gyroscope Y offset", (value as i16).to_string()),
+        GZOFFSET => ("gyroscope Z offset", (value as i16).to_string()),
+        HXOFFSET => ("magnetometer X offset", (value as i16).to_string()),
+        HYOFFSET => ("magnetometer Y offset", (value as i16).to_string()),
+        HZOFFSET => ("magnetometer Z offset", (value as i16).to_string()),
+        IICADDR => ("address", format!("0x{:02X}", value)),
+        BANDWIDTH => ("bandwidth", typed::<Bandwidth>(value)),
+        GYRORANGE => ("gyroscope range", typed::<GyroRange>(value)),
+        ACCRANGE => ("accelerometer range", typed::<AccRange>(value)),
+        ORIENT => ("orientation", typed::<Orientation>(value)),
+        AXIS6 => ("algorithm", typed::<AlgorithmAxes>(value)),
+        FILTK => ("filter K", value.to_string()),
+        ACCFILT => ("accelerometer filter", value.to_string()),
+        GYROFILT => ("gyroscope filter", value.to_string()),
+        MAGFILT => ("magnetometer filter", value.to_string()),
+        _ => (
+            Register::from_address(register).map_or("unknown register", |r| r.name()),
+            format!("0x{:04X}", value),
+        ),
+    }
+}
+
+/// Check whether a register holds a calibration offset (AXOFFSET..HZOFFSET)
+fn is_offset(register: u16) -> bool {
+    (AXOFFSET..=HZOFFSET).contains(&register)
+}
+
+/// Packet names of an output content, like "ACC | GYRO"
+fn content_text(content: OutputContent) -> String {
+    let names: Vec<_> = content.iter_names().map(|(name, _)| name).collect();
+    if names.is_empty() {
+        format!("none (0x{:04X})", content.register_value())
+    } else {
+        names.join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockTransport, model::SensorModel, profile::DeviceProfile, protocol::WitProtocol};
+
+    #[test]
+    fn test_diff_explains_changes() {
+        let mut snapshot = RegisterSnapshot::new(SensorModel::Wt901C485);
+        for &register in DeviceConfig::REGISTERS {
+            snapshot.set(register, 0).unwrap();
+        }
+        snapshot.set(RSW, RSW_ACC | RSW_ANGLE).unwrap();
+        snapshot.set(RRATE, RRATE_10HZ).unwrap();
+        let old = DeviceConfig::from_snapshot(&snapshot).unwrap();
+
+        let mut new = old;
+        new.bandwidth = Bandwidth::Hz21;
+        new.gyro_offset[2] = -12;
+        new.output_content |= OutputContent::GYRO;
+
+        let text: Vec<_> = old.diff(&new).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            text,
+            [
+                "output content: ACC | ANGLE -> ACC | GYRO | ANGLE",
+                "gyroscope Z offset: 0 -> -12",
+                "bandwidth: 256 Hz -> 21 Hz",
+            ]
+        );
+        assert!(old.diff(&old).is_empty());
+        assert_eq!(old.register_values().len(), DeviceConfig::REGISTERS.len());
+
+        snapshot.set(BANDWIDTH, 9).unwrap();
+        assert!(DeviceConfig::from_snapshot(&snapshot).is_err());
+    }
+
+    #[test]
+    fn test_clone_writes_only_differences() {
+        let sensor = |bandwidth: Bandwidth, gyro_z_offset: i16| {
+            let transport = MockTransport::new(WitProtocol::Modbus)
+                .with(RRATE, RRATE_10HZ)
+                .with(BANDWIDTH, bandwidth.into())
+                .with(GZOFFSET, gyro_z_offset as u16)
+                .with(ACCRANGE, AccRange::G4.into())
+                .with(GYRORANGE, GyroRange::Dps2000.into());
+            WitSensor::with_transport(transport)
+        };
+        let mut source = sensor(Bandwidth::Hz21, -12);
+        let mut target = sensor(Bandwidth::Hz256, 7);
+
+        // The target keeps its own calibration
+        let differences = source.clone_to(&mut target, false).unwrap();
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].to_string(), "bandwidth: 256 Hz -> 21 Hz");
+        assert_eq!(
+            target.transport().writes_after_unlock().unwrap(),
+            [(BANDWIDTH, Bandwidth::Hz21.into()), (SAVE, SAVE_PARAM)]
+        );
+        assert_eq!(target.read_config().unwrap().gyro_offset, [0, 0, 7]);
+        assert!(source.transport().writes.is_empty());
+
+        // Unless offsets are copied too
+        let differences = source.clone_to(&mut target, true).unwrap();
+        assert_eq!(differences.iter().map(|d| d.register).collect::<Vec<_>>(), [GZOFFSET]);
+        assert_eq!(target.read_config().unwrap(), source.read_config().unwrap());
+
+        // A configuration only fits sensors of the same model
+        let mut other = sensor(Bandwidth::Hz256, 7);
+        other.set_profile(&DeviceProfile::HWT905);
+        assert!(source.clone_to(&mut other, true).is_err());
+        assert!(other.transport().writes.is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{
+    config::ConfigDifference,
+    error::{WitError, WitResult},
+    modbus::MAX_SLAVE_ADDRESS,
+    registers::*,
+    sensor::WitSensor,
+    settings::*,
+    transaction::ConfigTransaction,
+    transport::Transport,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// Settings a sensor should have, as kept under version control
+///
+/// Every field is optional, only the settings present are applied or verified.
+/// Values use physical units where there are any:
+///
+/// ```toml
+/// baud = 115200
+/// address = 0x51
+/// output_rate = "10 Hz"
+/// bandwidth = 21           # Hz
+/// acc_range = 16           # g
+/// gyro_range = 2000        # °/s
+/// orientation = "horizontal"
+/// algorithm = "9-axis"
+/// output_content = ["ACC", "GYRO", "ANGLE"]
+/// acc_offset = [0, 0, 0]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Line speed in bits per second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baud: Option<u32>,
+    /// Modbus slave address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<u8>,
+    /// Output rate label, like "10 Hz", "once" or "off"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_rate: Option<String>,
+    /// Filter bandwidth in Hz
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bandwidth: Option<u16>,
+    /// Accelerometer full scale in g
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acc_range: Option<u16>,
+    /// Gyroscope full scale in °/s
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gyro_range: Option<u16>,
+    /// "horizontal" or "vertical"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<String>,
+    /// "9-axis" or "6-axis"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub algorithm: Option<String>,
+    /// Packet names of the RSW register, like ["ACC", "ANGLE"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_content: Option<Vec<String>>,
+    /// Raw accelerometer X, Y and Z offsets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acc_offset: Option<[i16; 3]>,
+    /// Raw gyroscope X, Y and Z offsets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gyro_offset: Option<[i16; 3]>,
+    /// Raw magnetometer X, Y and Z offsets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mag_offset: Option<[i16; 3]>,
+}
+
+impl ConfigFile {
+    /// Parse a TOML configuration
+    pub fn from_toml(text: &str) -> WitResult<Self> {
+        toml::from_str(text).map_err(|e| WitError::InvalidParameter(format!("Invalid configuration: {}", e)))
+    }
+
+    /// Parse a YAML configuration
+    pub fn from_yaml(text: &str) -> WitResult<Self> {
+        serde_yaml::from_str(text).map_err(|e| WitError::InvalidParameter(format!("Invalid configuration: {}", e)))
+    }
+
+    /// Write the configuration as TOML
+    pub fn to_toml(&self) -> WitResult<String> {
+        toml::to_string(self).map_err(|e| WitError::InvalidParameter(format!("Cannot write configuration: {}", e)))
+    }
+
+    /// Write the configuration as YAML
+    pub fn to_yaml(&self) -> WitResult<String> {
+        serde_yaml::to_string(self)
+            .map_err(|e| WitError::InvalidParameter(format!("Cannot write configuration: {}", e)))
+    }
+
+    /// Load a configuration file, YAML for `.yaml`/`.yml` and TOML otherwise
+    pub fn load(path: impl AsRef<Path>) -> WitResult<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => Self::from_yaml(&text),
+            _ => Self::from_toml(&text),
+        }
+    }
+
+    /// Register values the configuration asks for, in address order
+    /// BAUD and IICADDR are included when the file sets them
+    pub fn register_values(&self) -> WitResult<Vec<(u16, u16)>> {
+        let mut values = Vec::new();
+
+        if let Some(content) = &self.output_content {
+            let mut flags = OutputContent::empty();
+            for name in content {
+                flags |= OutputContent::from_name(&name.to_uppercase())
+                    .ok_or_else(|| WitError::InvalidParameter(format!("Unknown output content: {}", name)))?;
+            }
+            values.push((RSW, flags.register_value()));
+        }
+        if let Some(rate) = &self.output_rate {
+            values.push((RRATE, rate.parse::<OutputRate>()?.into()));
+        }
+        if let Some(baud) = self.baud {
+            values.push((BAUD, BaudRate::from_bits_per_second(baud)?.into()));
+        }
+        let offsets = [(AXOFFSET, self.acc_offset), (GXOFFSET, self.gyro_offset), (HXOFFSET, self.mag_offset)];
+        for (first, offset) in offsets {
+            if let Some(offset) = offset {
+                values.extend((0..3).map(|i| (first + i as u16, offset[i] as u16)));
+            }
+        }
+        if let Some(address) = self.address {
+            if !(1..=MAX_SLAVE_ADDRESS).contains(&address) {
+                return Err(WitError::InvalidParameter(format!("Invalid Modbus address: 0x{:02X}", address)));
+            }
+            values.push((IICADDR, address as u16));
+        }
+        if let Some(hz) = self.bandwidth {
+            values.push((BANDWIDTH, find(hz, Bandwidth::ALL, |b| b.hz() as f32, "bandwidth")?));
+        }
+        if let Some(dps) = self.gyro_range {
+            values.push((GYRORANGE, find(dps, GyroRange::ALL, |r| r.degrees_per_second(), "gyroscope range")?));
+        }
+        if let Some(g) = self.acc_range {
+            values.push((ACCRANGE, find(g, AccRange::ALL, |r| r.g(), "accelerometer range")?));
+        }
+        if let Some(orientation) = &self.orientation {
+            values.push((ORIENT, orientation.parse::<Orientation>()?.into()));
+        }
+        if let Some(algorithm) = &self.algorithm {
+            values.push((AXIS6, algorithm.parse::<AlgorithmAxes>()?.into()));
+        }
+
+        Ok(values)
+    }
+}
+
+impl<T: Transport> WitSensor<T> {
+    /// Report the settings of the sensor that differ from a configuration file
+    /// Each difference goes from the current value to the configured one
+    pub fn verify_config(&mut self, config: &ConfigFile) -> WitResult<Vec<ConfigDifference>> {
+        let wanted = config.register_values()?;
+        let registers: Vec<u16> = wanted.iter().map(|&(register, _)| register).collect();
+        let current = self.read_register_values(&registers)?;
+
+        Ok(wanted
+            .into_iter()
+            .zip(current)
+            .filter(|&((_, value), current)| value != current)
+            .map(|((register, value), current)| ConfigDifference::new(register, current, value))
+            .collect())
+    }
+
+    /// Make the sensor match a configuration file, writing only what differs
+    ///
+    /// Settings the model does not support are refused before anything is
+    /// written. The others are written and saved in one verified transaction
+    /// over the current link. A new address and then a new baud rate are
+    /// applied last, each followed to and verified. Returns what was changed.
+    pub fn apply_config(&mut self, config: &ConfigFile) -> WitResult<Vec<ConfigDifference>> {
+        for (register, _) in config.register_values()? {
+            self.check_supported(register)?;
+        }
+        let drift = self.verify_config(config)?;
+
+        let mut transaction = ConfigTransaction::new();
+        for (register, value) in config.register_values()? {
+            let link = register == BAUD || register == IICADDR;
+            if !link && drift.iter().any(|difference| difference.register == register) {
+                transaction.write(register, value);
+            }
+        }
+        transaction.commit(self)?;
+
+        if drift.iter().any(|difference| difference.register == IICADDR) {
+            if let Some(address) = config.address {
+                self.change_address(address)?;
+            }
+        }
+        if drift.iter().any(|difference| difference.register == BAUD) {
+            if let Some(baud) = config.baud {
+                self.change_baud_rate(BaudRate::from_bits_per_second(baud)?)?;
+            }
+        }
+
+        Ok(drift)
+    }
+}
+
+/// Register value of the setting whose physical value is `wanted`
+fn find<V: RegisterValue>(wanted: u16, values: &[V], physical: impl Fn(&V) -> f32, setting: &str) -> WitResult<u16> {
+    values
+        .iter()
+        .find(|value| physical(value) == wanted as f32)
+        .map(|&value| value.into())
+        .ok_or_else(|| WitError::InvalidParameter(format!("Unsupported {}: {}", setting, wanted)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockTransport, profile::DeviceProfile, protocol::WitProtocol};
+
+    /// Sensor at 0x50 and 9600 baud with a 10 Hz output rate and 21 Hz bandwidth
+    fn sensor() -> WitSensor<MockTransport> {
+        let mut transport = MockTransport::new(WitProtocol::Modbus)
+            .with(IICADDR, 0x50)
+            .with(BAUD, WIT_BAUD_9600)
+            .with(RRATE, RRATE_10HZ)
+            .with(BANDWIDTH, Bandwidth::Hz21.into());
+        transport.address = Some(0x50);
+        transport.baud = Some(9600);
+        WitSensor::with_transport(transport)
+    }
+
+    #[test]
+    fn test_toml_and_yaml_give_the_same_registers() {
+        let toml = r#"
+            address = 0x51
+            output_rate = "10 Hz"
+            bandwidth = 21
+            acc_range = 16
+            output_content = ["acc", "GYRO"]
+            gyro_offset = [0, 0, -12]
+        "#;
+        let yaml = "
+            address: 0x51
+            output_rate: 10hz
+            bandwidth: 21
+            acc_range: 16
+            output_content: [acc, GYRO]
+            gyro_offset: [0, 0, -12]
+        ";
+
+        let config = ConfigFile::from_toml(toml).unwrap();
+        let from_yaml = ConfigFile::from_yaml(yaml).unwrap();
+        assert_eq!(from_yaml.register_values().unwrap(), config.register_values().unwrap());
+        assert_eq!(ConfigFile::from_toml(&config.to_toml().unwrap()).unwrap(), config);
+        assert_eq!(
+            config.register_values().unwrap(),
+            [
+                (RSW, RSW_ACC | RSW_GYRO),
+                (RRATE, RRATE_10HZ),
+                (GXOFFSET, 0),
+                (GYOFFSET, 0),
+                (GZOFFSET, -12i16 as u16),
+                (IICADDR, 0x51),
+                (BANDWIDTH, 4),
+                (ACCRANGE, 3),
+            ]
+        );
+
+        assert!(ConfigFile::from_toml("bandwith = 21").is_err());
+        assert!(ConfigFile::from_toml("bandwidth = 20").unwrap().register_values().is_err());
+    }
+
+    #[test]
+    fn test_apply_writes_only_drift() {
+        let mut sensor = sensor();
+        let config = ConfigFile::from_toml("output_rate = \"10 Hz\"\nbandwidth = 21\nacc_range = 16").unwrap();
+
+        let drift = sensor.verify_config(&config).unwrap();
+        assert_eq!(drift.iter().map(|d| d.register).collect::<Vec<_>>(), [ACCRANGE]);
+        assert_eq!(sensor.apply_config(&config).unwrap(), drift);
+        assert_eq!(
+            sensor.transport().writes_after_unlock().unwrap(),
+            [(ACCRANGE, AccRange::G16.into()), (SAVE, SAVE_PARAM)]
+        );
+        assert!(sensor.verify_config(&config).unwrap().is_empty());
+
+        // Nothing to do the second time
+        let count = sensor.transport().writes.len();
+        assert!(sensor.apply_config(&config).unwrap().is_empty());
+        assert_eq!(sensor.transport().writes.len(), count);
+    }
+
+    #[test]
+    fn test_apply_changes_address_then_baud_rate() {
+        let mut sensor = sensor();
+        let config = ConfigFile::from_toml("baud = 115200\naddress = 0x51\nbandwidth = 44").unwrap();
+
+        assert_eq!(sensor.apply_config(&config).unwrap().len(), 3);
+        let writes = &sensor.transport().writes;
+        let position = |write| writes.iter().position(|&w| w == write).unwrap();
+        let bandwidth = position((BANDWIDTH, Bandwidth::Hz44.into()));
+        let address = position((IICADDR, 0x51));
+        let baud = position((BAUD, BaudRate::B115200.into()));
+        assert!(bandwidth < address && address < baud);
+
+        // The link followed the sensor and the settings survive a reboot
+        let transport = sensor.transport();
+        assert_eq!((transport.address, transport.baud), (Some(0x51), Some(115200)));
+        assert_eq!(transport.saved[&IICADDR], 0x51);
+        assert_eq!(transport.saved[&BAUD], u16::from(BaudRate::B115200));
+        assert!(sensor.verify_config(&config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_apply_refuses_unsupported_settings() {
+        let mut sensor = sensor();
+        sensor.set_profile(&DeviceProfile::JY61);
+        let config = ConfigFile::from_toml("bandwidth = 44").unwrap();
+
+        assert!(matches!(sensor.apply_config(&config), Err(WitError::InvalidParameter(_))));
+        assert!(sensor.transport().writes.is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{registers::register_name, transaction::RegisterMismatch};
 use std::fmt;
 
 /// Error types for WitMotion sensor operations
@@ -11,6 +12,19 @@ pub enum WitError {
     CrcMismatch,
     /// Sensor not found during auto-scan
     SensorNotFound,
+    /// The sensor answered a Modbus request with an exception code
+    ModbusException { function: u8, code: u8 },
+    /// Another sensor already answers at this Modbus address
+    AddressInUse(u8),
+    /// Registers did not read back the values written to them
+    VerificationFailed(Vec<RegisterMismatch>),
+    /// A transaction failed and some previous values could not be written back
+    RollbackFailed {
+        /// Why the transaction was rolled back
+        cause: Box<WitError>,
+        /// Registers left with the new value, and why writing the old one failed
+        failures: Vec<(u16, WitError)>,
+    },
     /// Generic I/O error
     Io(std::io::Error),
 }
@@ -22,6 +36,32 @@ impl fmt::Display for WitError {
             WitError::Timeout => write!(f, "Communication timeout"),
             WitError::CrcMismatch => write!(f, "CRC checksum mismatch"),
             WitError::SensorNotFound => write!(f, "Sensor not found"),
+            WitError::ModbusException { function, code } => {
+                write!(f, "Modbus exception 0x{:02X} for function 0x{:02X}", code, function)
+            }
+            WitError::AddressInUse(address) => {
+                write!(f, "Address 0x{:02X} is already in use on the bus", address)
+            }
+            WitError::VerificationFailed(mismatches) => {
+                write!(f, "Verification failed for ")?;
+                for (i, mismatch) in mismatches.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", mismatch)?;
+                }
+                Ok(())
+            }
+            WitError::RollbackFailed { cause, failures } => {
+                write!(f, "{}; rolling back failed for ", cause)?;
+                for (i, (register, error)) in failures.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} ({})", register_name(*register), error)?;
+                }
+                Ok(())
+            }
             WitError::Io(e) => write!(f, "I/O error: {}", e),
         }
     }
@@ -31,6 +71,7 @@ impl std::error::Error for WitError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             WitError::Io(e) => Some(e),
+            WitError::RollbackFailed { cause, .. } => Some(cause.as_ref()),
             _ => None,
         }
     }
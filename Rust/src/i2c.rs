@@ -0,0 +1,186 @@
+use crate::{
+    error::{WitError, WitResult},
+    protocol::{RegisterBlock, WitProtocol},
+    transport::Transport,
+};
+use std::collections::VecDeque;
+
+/// Default I2C address of WitMotion sensors (IICADDR)
+pub const DEFAULT_I2C_ADDRESS: u8 = 0x50;
+
+/// Largest register address reachable over I2C (register addresses are one byte)
+pub const MAX_I2C_REGISTER: u16 = 0xFF;
+
+/// Raw I2C bus that register transfers are made over
+///
+/// Mirrors the `WitI2cRead`/`WitI2cWrite` callbacks of the C SDK, with 7-bit addresses.
+pub trait I2cBus {
+    /// Write the register address, then read `buffer.len()` bytes from the device
+    fn read(&mut self, address: u8, register: u8, buffer: &mut [u8]) -> WitResult<()>;
+
+    /// Write the register address followed by `data` to the device
+    fn write(&mut self, address: u8, register: u8, data: &[u8]) -> WitResult<()>;
+}
+
+/// Transport for sensors attached over I2C
+///
+/// Each register is two bytes, low byte first. Reads complete synchronously,
+/// their values are handed out by `receive` afterwards.
+pub struct I2cTransport<B: I2cBus> {
+    bus: B,
+    address: u8,
+    pending: VecDeque<RegisterBlock>,
+}
+
+impl<B: I2cBus> I2cTransport<B> {
+    /// Create an I2C transport talking to the sensor at the given address
+    pub fn new(bus: B, address: u8) -> Self {
+        Self {
+            bus,
+            address,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Get the I2C address of the sensor
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Change the I2C address requests are sent to
+    pub fn set_address(&mut self, address: u8) {
+        self.address = address;
+    }
+
+    /// Get the underlying I2C bus
+    pub fn bus(&self) -> &B {
+        &self.bus
+    }
+
+    /// Get the underlying I2C bus mutably
+    pub fn bus_mut(&mut self) -> &mut B {
+        &mut self.bus
+    }
+}
+
+impl<B: I2cBus> Transport for I2cTransport<B> {
+    fn protocol(&self) -> WitProtocol {
+        WitProtocol::I2c
+    }
+
+    fn read_registers(&mut self, start_register: u16, count: u16) -> WitResult<()> {
+        let last = count.checked_sub(1).and_then(|n| start_register.checked_add(n));
+        if last.is_none_or(|last| last > MAX_I2C_REGISTER) {
+            return Err(WitError::InvalidParameter(format!(
+                "Invalid I2C register range: 0x{:02X} + {}",
+                start_register, count
+            )));
+        }
+
+        let mut buffer = vec![0u8; count as usize * 2];
+        self.bus.read(self.address, start_register as u8, &mut buffer)?;
+
+        let values = buffer
+            .chunks_exact(2)
+            .map(|bytes| i16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect();
+        self.pending.push_back((start_register, values));
+        Ok(())
+    }
+
+    fn write_register(&mut self, register: u16, value: u16) -> WitResult<()> {
+        if register > MAX_I2C_REGISTER {
+            return Err(WitError::InvalidParameter(format!(
+                "Invalid I2C register: 0x{:02X}",
+                register
+            )));
+        }
+
+        self.bus.write(self.address, register as u8, &value.to_le_bytes())
+    }
+
+    fn receive(&mut self) -> WitResult<Option<RegisterBlock>> {
+        Ok(self.pending.pop_front())
+    }
+
+    fn clear_input(&mut self) -> WitResult<()> {
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{registers::*, sensor::WitSensor, settings::AccRange};
+    use std::collections::HashMap;
+
+    /// Sensor register file, addressed per 16-bit register like the real device
+    #[derive(Default)]
+    struct MockBus {
+        registers: HashMap<u8, u16>,
+    }
+
+    impl I2cBus for MockBus {
+        fn read(&mut self, address: u8, register: u8, buffer: &mut [u8]) -> WitResult<()> {
+            if address != DEFAULT_I2C_ADDRESS {
+                return Err(WitError::SensorNotFound);
+            }
+            for (i, bytes) in buffer.chunks_mut(2).enumerate() {
+                let value = self.registers.get(&(register + i as u8)).copied().unwrap_or(0);
+                bytes.copy_from_slice(&value.to_le_bytes());
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, address: u8, register: u8, data: &[u8]) -> WitResult<()> {
+            if address != DEFAULT_I2C_ADDRESS {
+                return Err(WitError::SensorNotFound);
+            }
+            self.registers.insert(register, u16::from_le_bytes([data[0], data[1]]));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_register_access() {
+        let mut bus = MockBus::default();
+        bus.registers.insert(AX as u8, 2048);
+        bus.registers.insert(AZ as u8, (-2048i16) as u16);
+        bus.registers.insert(YAW as u8, 16384);
+
+        let mut sensor = WitSensor::with_transport(I2cTransport::new(bus, DEFAULT_I2C_ADDRESS));
+        let data = sensor.read_sensor_data().unwrap();
+        assert_eq!(data.accelerometer, [1.0, 0.0, -1.0]);
+        assert_eq!(data.angles[2], 90.0);
+
+        sensor.write_register(KEY, KEY_UNLOCK).unwrap();
+        assert_eq!(sensor.transport().bus().registers[&(KEY as u8)], KEY_UNLOCK);
+
+        assert!(sensor.read_registers(0xFE, 4).is_err());
+        assert!(sensor.read_registers(0xFE, 0).is_err());
+        // Would overflow u16 arithmetic
+        assert!(sensor.read_registers(0x10, u16::MAX).is_err());
+        assert!(sensor.read_registers(u16::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_scaling_follows_configured_ranges() {
+        let mut bus = MockBus::default();
+        bus.registers.insert(ACCRANGE as u8, 1);
+        bus.registers.insert(GYRORANGE as u8, 0);
+        bus.registers.insert(AX as u8, 8192);
+        bus.registers.insert(GX as u8, 16384);
+
+        let mut sensor = WitSensor::with_transport(I2cTransport::new(bus, DEFAULT_I2C_ADDRESS));
+        sensor.init().unwrap();
+        assert_eq!(sensor.ranges().acc, AccRange::G4);
+        let data = sensor.read_sensor_data().unwrap();
+        assert_eq!(data.accelerometer[0], 1.0);
+        assert_eq!(data.gyroscope[0], 125.0);
+
+        // A configuration write changes the scaling right away
+        sensor.set_acc_range(AccRange::G16).unwrap();
+        assert_eq!(sensor.read_sensor_data().unwrap().accelerometer[0], 4.0);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    error::{WitError, WitResult},
+    i2c::{I2cBus, I2cTransport},
+    sensor::WitSensor,
+};
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    os::fd::AsRawFd,
+};
+
+/// ioctl selecting the slave address used by following transfers (linux/i2c-dev.h)
+const I2C_SLAVE: libc::c_ulong = 0x0703;
+/// ioctl performing an SMBus transfer (linux/i2c-dev.h)
+const I2C_SMBUS: libc::c_ulong = 0x0720;
+
+const I2C_SMBUS_READ: u8 = 1;
+const I2C_SMBUS_WRITE: u8 = 0;
+const I2C_SMBUS_WORD_DATA: u32 = 3;
+
+/// Mirror of `union i2c_smbus_data` (linux/i2c.h)
+#[repr(C)]
+union SmbusData {
+    byte: u8,
+    word: u16,
+    block: [u8; 34],
+}
+
+/// Mirror of `struct i2c_smbus_ioctl_data` (linux/i2c-dev.h)
+#[repr(C)]
+struct SmbusIoctlData {
+    read_write: u8,
+    command: u8,
+    size: u32,
+    data: *mut SmbusData,
+}
+
+/// Linux i2c-dev bus (e.g. `/dev/i2c-1`)
+///
+/// Registers are transferred as SMBus words, one per register, which the
+/// sensors answer low byte first. This also works with the kernel `i2c-stub`.
+pub struct WitI2cDev {
+    file: File,
+    address: Option<u8>,
+}
+
+impl WitI2cDev {
+    /// Open an I2C bus device
+    pub fn open(device_path: &str) -> WitResult<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(device_path)?;
+        Ok(Self { file, address: None })
+    }
+
+    /// Point following transfers at the given slave address
+    fn select(&mut self, address: u8) -> WitResult<()> {
+        if self.address == Some(address) {
+            return Ok(());
+        }
+
+        // SAFETY: I2C_SLAVE takes the address by value
+        let result = unsafe {
+            libc::ioctl(self.file.as_raw_fd(), I2C_SLAVE as _, address as libc::c_ulong)
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        self.address = Some(address);
+        Ok(())
+    }
+
+    fn smbus(&mut self, read_write: u8, command: u8, data: &mut SmbusData) -> WitResult<()> {
+        let mut request = SmbusIoctlData {
+            read_write,
+            command,
+            size: I2C_SMBUS_WORD_DATA,
+            data,
+        };
+
+        // SAFETY: request and data outlive the call and match the kernel layout
+        let result = unsafe {
+            libc::ioctl(self.file.as_raw_fd(), I2C_SMBUS as _, &mut request as *mut SmbusIoctlData)
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+impl I2cBus for WitI2cDev {
+    fn read(&mut self, address: u8, register: u8, buffer: &mut [u8]) -> WitResult<()> {
+        self.select(address)?;
+
+        for (i, bytes) in buffer.chunks_mut(2).enumerate() {
+            let mut data = SmbusData { word: 0 };
+            self.smbus(I2C_SMBUS_READ, register.wrapping_add(i as u8), &mut data)?;
+
+            // SAFETY: a word transfer fills the word member
+            let word = unsafe { data.word }.to_le_bytes();
+            bytes.copy_from_slice(&word[..bytes.len()]);
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, address: u8, register: u8, data: &[u8]) -> WitResult<()> {
+        let [low, high] = data else {
+            return Err(WitError::InvalidParameter(format!(
+                "I2C register writes take 2 bytes, got {}",
+                data.len()
+            )));
+        };
+
+        self.select(address)?;
+        let mut data = SmbusData {
+            word: u16::from_le_bytes([*low, *high]),
+        };
+        self.smbus(I2C_SMBUS_WRITE, register, &mut data)
+    }
+}
+
+impl WitSensor<I2cTransport<WitI2cDev>> {
+    /// Open a sensor on a Linux I2C bus
+    /// Pass `DEFAULT_I2C_ADDRESS` (0x50) unless IICADDR was changed
+    pub fn open_i2c(device_path: &str, address: u8) -> WitResult<Self> {
+        let bus = WitI2cDev::open(device_path)?;
+        Ok(Self::with_transport(I2cTransport::new(bus, address)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{i2c::DEFAULT_I2C_ADDRESS, registers::*};
+
+    /// Needs the kernel I2C stub with a chip at the sensor address:
+    /// `modprobe i2c-dev && modprobe i2c-stub chip_addr=0x50`
+    /// Set WIT_I2C_DEVICE if the stub is not /dev/i2c-0
+    #[test]
+    #[ignore]
+    fn test_i2c_stub() {
+        let device = std::env::var("WIT_I2C_DEVICE").unwrap_or_else(|_| "/dev/i2c-0".to_string());
+        let mut sensor = WitSensor::open_i2c(&device, DEFAULT_I2C_ADDRESS).unwrap();
+
+        sensor.write_register(BANDWIDTH, 4).unwrap();
+        sensor.write_register(GYRORANGE, 0xB588).unwrap();
+        sensor.read_registers(BANDWIDTH, 2).unwrap();
+        sensor.process_incoming_data().unwrap();
+
+        assert_eq!(sensor.get_register(BANDWIDTH), Some(4));
+        assert_eq!(sensor.get_register(GYRORANGE), Some(0xB588u16 as i16));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    error::WitResult,
+    model::SensorModel,
+    registers::*,
+    sensor::WitSensor,
+    transport::Transport,
+};
+use std::fmt;
+
+/// Firmware version as decoded by the vendor tools
+///
+/// VERSION (0x2E) and the register after it form a 32-bit value. With bit 31
+/// set it holds a major.minor.patch version, otherwise VERSION is a plain number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FirmwareVersion {
+    Semantic { major: u32, minor: u8, patch: u8 },
+    Legacy(u16),
+}
+
+impl FirmwareVersion {
+    /// Decode the VERSION register and the one after it
+    pub fn from_registers(low: u16, high: u16) -> Self {
+        let value = (high as u32) << 16 | low as u32;
+        if value & 0x8000_0000 == 0 {
+            return FirmwareVersion::Legacy(low);
+        }
+
+        FirmwareVersion::Semantic {
+            major: (value >> 14) & 0x1FFFF,
+            minor: ((value >> 8) & 0x3F) as u8,
+            patch: (value & 0xFF) as u8,
+        }
+    }
+}
+
+impl fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FirmwareVersion::Semantic { major, minor, patch } => write!(f, "{}.{}.{}", major, minor, patch),
+            FirmwareVersion::Legacy(version) => write!(f, "{}", version),
+        }
+    }
+}
+
+/// Identification of a single unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub model: SensorModel,
+    pub firmware_version: FirmwareVersion,
+    /// Serial number printed on the unit (NUMBERID1..6)
+    pub serial_number: String,
+    /// Chip ID (CHIPIDH:CHIPIDL)
+    pub chip_id: u32,
+}
+
+impl fmt::Display for DeviceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, firmware {}, serial number {}, chip ID 0x{:08X}",
+            self.model, self.firmware_version, self.serial_number, self.chip_id
+        )
+    }
+}
+
+/// Decode the serial number: NUMBERID1..6 hold two ASCII characters each, low byte first
+pub fn decode_serial_number(registers: &[u16]) -> String {
+    registers
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .take_while(|&byte| byte != 0)
+        .filter(|byte| byte.is_ascii_graphic())
+        .map(char::from)
+        .collect()
+}
+
+impl<T: Transport> WitSensor<T> {
+    /// Read the firmware version, serial number and chip ID
+    pub fn device_info(&mut self) -> WitResult<DeviceInfo> {
+        let serial_registers = [NUMBERID1, NUMBERID2, NUMBERID3, NUMBERID4, NUMBERID5, NUMBERID6];
+        let mut registers = vec![VERSION, VERSION + 1, CHIPIDL, CHIPIDH];
+        registers.extend_from_slice(&serial_registers);
+
+        let values = self.read_register_values(&registers)?;
+        Ok(DeviceInfo {
+            model: self.profile().model,
+            firmware_version: FirmwareVersion::from_registers(values[0], values[1]),
+            serial_number: decode_serial_number(&values[4..]),
+            chip_id: (values[3] as u32) << 16 | values[2] as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identification_decoding() {
+        assert_eq!(FirmwareVersion::from_registers(0x1234, 0).to_string(), "4660");
+        // Bit 31 set: major 2, minor 5, patch 17
+        let value: u32 = 0x8000_0000 | 2 << 14 | 5 << 8 | 17;
+        let version = FirmwareVersion::from_registers(value as u16, (value >> 16) as u16);
+        assert_eq!(version, FirmwareVersion::Semantic { major: 2, minor: 5, patch: 17 });
+        assert_eq!(version.to_string(), "2.5.17");
+
+        assert_eq!(decode_serial_number(&[0x5457, 0x3231, 0x0033, 0x4141]), "WT123");
+    }
+}
@@ -0,0 +1,76 @@
+use crate::normal::COMMAND_HEADER;
+
+/// Commands understood by JY61 6-axis sensors
+///
+/// JY61 boards have no register map to write to. They take fixed three-byte
+/// `0xFF 0xAA cmd` commands instead, with no unlock sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Jy61Command {
+    /// Reset the Z axis angle to zero
+    ZeroYaw,
+    /// Toggle between sleep and normal operation
+    SleepToggle,
+    /// Use the serial port (disables I2C)
+    SerialMode,
+    /// Use I2C (disables the serial port)
+    I2cMode,
+    /// Switch to 115200 baud with 100Hz output
+    Baud115200,
+    /// Switch to 9600 baud with 20Hz output
+    Baud9600,
+    /// Mounted horizontally
+    HorizontalInstall,
+    /// Mounted vertically
+    VerticalInstall,
+    /// Calibrate the accelerometer (keep the sensor level and still)
+    AccCalibration,
+}
+
+impl Jy61Command {
+    /// Command byte sent after the 0xFF 0xAA header
+    pub fn code(&self) -> u8 {
+        match self {
+            Jy61Command::ZeroYaw => 0x52,
+            Jy61Command::SleepToggle => 0x60,
+            Jy61Command::SerialMode => 0x61,
+            Jy61Command::I2cMode => 0x62,
+            Jy61Command::Baud115200 => 0x63,
+            Jy61Command::Baud9600 => 0x64,
+            Jy61Command::HorizontalInstall => 0x65,
+            Jy61Command::VerticalInstall => 0x66,
+            Jy61Command::AccCalibration => 0x67,
+        }
+    }
+
+    /// Encode the command as sent on the wire
+    pub fn encode(&self) -> [u8; 3] {
+        [COMMAND_HEADER[0], COMMAND_HEADER[1], self.code()]
+    }
+
+    /// Baud rate the sensor switches to after this command, if it changes it
+    pub fn baud_rate(&self) -> Option<u32> {
+        match self {
+            Jy61Command::Baud115200 => Some(115200),
+            Jy61Command::Baud9600 => Some(9600),
+            _ => None,
+        }
+    }
+}
+
+/// Convert a raw JY61 temperature reading to °C (MPU6050 formula)
+pub fn jy61_temperature(raw: i16) -> f32 {
+    raw as f32 / 340.0 + 36.53
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(Jy61Command::AccCalibration.encode(), [0xFF, 0xAA, 0x67]);
+        assert_eq!(Jy61Command::Baud115200.encode(), [0xFF, 0xAA, 0x63]);
+        assert_eq!(Jy61Command::Baud9600.baud_rate(), Some(9600));
+        assert_eq!(Jy61Command::ZeroYaw.baud_rate(), None);
+    }
+}
@@ -1,16 +1,62 @@
 //! WitMotion Modbus sensor interface library
 //! 
 //! This library provides functionality to interface with WitMotion IMU sensors
-//! using the Modbus protocol over RS485 serial communication.
+//! using the Modbus protocol over RS485 serial communication, the WIT
+//! "normal" protocol streamed by TTL/RS232 variants, CAN or I2C.
 
 pub mod registers;
+pub mod at_can;
+pub mod backup;
+pub mod bus;
+pub mod can;
+pub mod commission;
+pub mod config;
+pub mod config_file;
+pub mod i2c;
+#[cfg(target_os = "linux")]
+pub mod i2cdev;
+pub mod info;
+pub mod jy61;
+#[cfg(test)]
+mod mock;
 pub mod modbus;
+pub mod model;
+pub mod normal;
+pub mod planner;
+pub mod poll;
+pub mod profile;
+pub mod protocol;
+pub mod scan;
 pub mod sensor;
 pub mod serial;
+pub mod settings;
+#[cfg(target_os = "linux")]
+pub mod socketcan;
+pub mod transaction;
+pub mod transport;
 pub mod error;
 
+pub use backup::{RegisterSnapshot, RestoreFilter};
+pub use bus::{BusManager, BusReading};
+pub use commission::{AddressMap, Commissioner};
+pub use config::{ConfigDifference, DeviceConfig};
+pub use config_file::ConfigFile;
 pub use error::{WitError, WitResult};
-pub use sensor::{WitSensor, SensorData, DataUpdateFlags};
+pub use info::{DeviceInfo, FirmwareVersion};
+pub use jy61::Jy61Command;
+pub use model::SensorModel;
+pub use planner::ReadPlanner;
+pub use poll::{PollGroup, PollScheduler};
+pub use profile::{Capabilities, DeviceProfile};
+pub use protocol::WitProtocol;
+pub use scan::{BusDevice, ScanOptions, ScanResult};
+pub use sensor::{WitSensor, SensorData, GpsData, DataUpdateFlags, MeasurementRanges};
+pub use settings::{
+    AccRange, AlgorithmAxes, BaudRate, Bandwidth, CalibrationMode, GyroRange, Orientation,
+    OutputContent, OutputRate, RegisterValue,
+};
+pub use transaction::{ConfigTransaction, MismatchPolicy, RegisterMismatch};
+pub use transport::{SerialTransport, Transport};
 pub use registers::*;
 
 /// Common baud rates for auto-scanning
@@ -23,3 +69,6 @@ pub const DEFAULT_POLL_INTERVAL_MS: u64 = 500;
 
 /// Default number of registers to read (covers accelerometer, gyroscope, and angles)
 pub const DEFAULT_READ_COUNT: u16 = 12;
+
+/// Default number of registers to read from WT905x sensors (up to the 32-bit angles and temperature)
+pub const DEFAULT_READ_COUNT_905X: u16 = 16;
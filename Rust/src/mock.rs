@@ -0,0 +1,193 @@
+use crate::{
+    error::{WitError, WitResult},
+    modbus::{BROADCAST_ADDRESS, MAX_READ_COUNT},
+    protocol::{RegisterBlock, WitProtocol},
+    registers::*,
+    settings::BaudRate,
+    transport::Transport,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+};
+
+/// Transport answering from a simulated register file, for tests
+///
+/// Like the real sensors, configuration writes are ignored until KEY is
+/// unlocked, SAVE stores the register file for the next reboot and the
+/// sensor only hears the host while both use the baud rate in BAUD and,
+/// over Modbus, requests go to the address in IICADDR or to broadcast.
+pub(crate) struct MockTransport {
+    protocol: WitProtocol,
+    /// Line speed of the host, None for links without one
+    pub baud: Option<u32>,
+    /// Slave address requests go to, None for links without one
+    pub address: Option<u8>,
+    /// Addresses of other sensors on the bus, which only answer reads
+    pub neighbours: Vec<u8>,
+    /// Register values of the simulated sensor, missing ones read as 0
+    pub registers: HashMap<u16, u16>,
+    /// Register values kept over a reboot
+    pub saved: HashMap<u16, u16>,
+    /// Every write sent, in order, including the ignored ones
+    pub writes: Vec<(u16, u16)>,
+    /// Registers that keep their value when written
+    pub read_only: Vec<u16>,
+    /// Registers answered per request, reads are split into several like the
+    /// WIT normal protocol when set
+    pub registers_per_request: Option<u16>,
+    /// Reads answered before every further read gets a Modbus exception
+    pub reads_before_exception: Option<usize>,
+    /// Writes accepted before every further write fails
+    pub writes_before_error: Option<usize>,
+    unlocked: bool,
+    pending: VecDeque<WitResult<RegisterBlock>>,
+}
+
+impl MockTransport {
+    /// Simulate a sensor speaking `protocol`
+    pub fn new(protocol: WitProtocol) -> Self {
+        Self {
+            protocol,
+            baud: None,
+            address: None,
+            neighbours: Vec::new(),
+            registers: HashMap::new(),
+            saved: HashMap::new(),
+            writes: Vec::new(),
+            read_only: Vec::new(),
+            registers_per_request: None,
+            reads_before_exception: None,
+            writes_before_error: None,
+            unlocked: false,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Set a register of the simulated sensor, as saved
+    pub fn with(mut self, register: u16, value: u16) -> Self {
+        self.registers.insert(register, value);
+        self.saved.insert(register, value);
+        self
+    }
+
+    /// Get a register of the simulated sensor
+    pub fn get(&self, register: u16) -> u16 {
+        self.registers.get(&register).copied().unwrap_or(0)
+    }
+
+    /// Writes sent after the last unlock, None if KEY was never unlocked
+    pub fn writes_after_unlock(&self) -> Option<&[(u16, u16)]> {
+        let unlock = self.writes.iter().rposition(|&write| write == (KEY, KEY_UNLOCK))?;
+        Some(&self.writes[unlock + 1..])
+    }
+
+    /// Check whether the sensor can hear the host
+    fn in_reach(&self) -> bool {
+        let baud = match (self.baud, BaudRate::try_from(self.get(BAUD))) {
+            (Some(host), Ok(sensor)) => host == sensor.bits_per_second(),
+            _ => true,
+        };
+        let address = match self.address {
+            Some(address) => address == BROADCAST_ADDRESS || address as u16 == self.get(IICADDR),
+            None => true,
+        };
+        baud && address
+    }
+
+    fn countdown(limit: &mut Option<usize>) -> bool {
+        match limit {
+            Some(0) => true,
+            Some(left) => {
+                *left -= 1;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    fn protocol(&self) -> WitProtocol {
+        self.protocol
+    }
+
+    fn read_registers(&mut self, start_register: u16, count: u16) -> WitResult<()> {
+        if let Some(address) = self.address.filter(|address| self.neighbours.contains(address)) {
+            let value = |register| if register == IICADDR { address as i16 } else { 0 };
+            let values = (start_register..start_register + count).map(value).collect();
+            self.pending.push_back(Ok((start_register, values)));
+            return Ok(());
+        }
+        if !self.in_reach() {
+            return Ok(());
+        }
+        if Self::countdown(&mut self.reads_before_exception) {
+            self.pending.push_back(Err(WitError::ModbusException { function: FUNC_READ, code: 0x02 }));
+            return Ok(());
+        }
+
+        // Every request is answered with a full block, even past the end of the read
+        let per_request = self.registers_per_request.unwrap_or(count);
+        for start in (start_register..start_register + count).step_by(per_request.max(1) as usize) {
+            let values = (start..start + per_request).map(|r| self.get(r) as i16).collect();
+            self.pending.push_back(Ok((start, values)));
+        }
+        Ok(())
+    }
+
+    fn write_register(&mut self, register: u16, value: u16) -> WitResult<()> {
+        if Self::countdown(&mut self.writes_before_error) {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "adapter unplugged").into());
+        }
+        self.writes.push((register, value));
+        if !self.in_reach() {
+            return Ok(());
+        }
+
+        match register {
+            KEY => self.unlocked = value == KEY_UNLOCK,
+            _ if !self.unlocked || self.read_only.contains(&register) => {}
+            SAVE if value == SAVE_PARAM => self.saved = self.registers.clone(),
+            SAVE if value == SAVE_SWRST => {
+                self.registers = self.saved.clone();
+                self.unlocked = false;
+            }
+            _ => {
+                self.registers.insert(register, value);
+            }
+        }
+        Ok(())
+    }
+
+    fn registers_per_request(&self) -> u16 {
+        self.registers_per_request.unwrap_or(MAX_READ_COUNT)
+    }
+
+    fn baud_rate(&self) -> Option<u32> {
+        self.baud
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> WitResult<()> {
+        self.baud = Some(baud_rate);
+        Ok(())
+    }
+
+    fn modbus_address(&self) -> Option<u8> {
+        self.address
+    }
+
+    fn set_modbus_address(&mut self, address: u8) -> WitResult<()> {
+        self.address = Some(address);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> WitResult<Option<RegisterBlock>> {
+        self.pending.pop_front().transpose()
+    }
+
+    fn clear_input(&mut self) -> WitResult<()> {
+        self.pending.clear();
+        Ok(())
+    }
+}
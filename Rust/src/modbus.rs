@@ -1,9 +1,27 @@
-use crate::error::{WitError, WitResult};
+use crate::{
+    error::{WitError, WitResult},
+    registers::{FUNC_READ, FUNC_WRITE},
+};
 use crc::{Crc, CRC_16_MODBUS};
 
 /// Modbus CRC calculator
 const MODBUS_CRC: Crc<u16> = Crc::<u16>::new(&CRC_16_MODBUS);
 
+/// Address sensors ship with
+pub const DEFAULT_SLAVE_ADDRESS: u8 = 0x50;
+
+/// Address every slave on the bus accepts requests for
+pub const BROADCAST_ADDRESS: u8 = 0xFF;
+
+/// Highest address a Modbus slave can be given
+pub const MAX_SLAVE_ADDRESS: u8 = 247;
+
+/// Most registers a single read may request
+pub const MAX_READ_COUNT: u16 = 125;
+
+/// Set in the function code of an exception response
+const EXCEPTION_FLAG: u8 = 0x80;
+
 /// Modbus protocol handler for WitMotion sensors
 pub struct ModbusProtocol {
     slave_address: u8,
@@ -21,6 +39,11 @@ impl ModbusProtocol {
         }
     }
 
+    /// Get the slave address requests are sent to
+    pub fn slave_address(&self) -> u8 {
+        self.slave_address
+    }
+
     /// Generate a Modbus read request
     pub fn generate_read_request(&mut self, start_register: u16, num_registers: u16) -> Vec<u8> {
         let mut frame = Vec::with_capacity(8);
@@ -63,29 +86,63 @@ impl ModbusProtocol {
     }
 
     /// Process incoming byte and return parsed register data if complete frame received
+    ///
+    /// Write echoes are checked and consumed without returning data, exception
+    /// responses are returned as `WitError::ModbusException`.
     pub fn process_byte(&mut self, byte: u8) -> WitResult<Option<(u16, Vec<i16>)>> {
         self.data_buffer.push(byte);
 
-        // Need at least 5 bytes for a valid response (addr + func + len + 2*CRC)
-        if self.data_buffer.len() < 5 {
+        // Need the function code and the byte after it to know the frame length
+        if self.data_buffer.len() < 3 {
             return Ok(None);
         }
 
-        // Check if we have enough bytes for a complete frame
-        if self.data_buffer.len() >= 3 {
-            let expected_length = self.data_buffer[2] as usize + 5; // data length + overhead
-            
-            if self.data_buffer.len() < expected_length {
-                return Ok(None); // Wait for more data
+        let function = self.data_buffer[1];
+        let expected_length = match function {
+            // addr + func + len + data + 2*CRC
+            FUNC_READ => self.data_buffer[2] as usize + 5,
+            // addr + func + register + value + 2*CRC
+            FUNC_WRITE => 8,
+            // addr + func + exception code + 2*CRC
+            _ if function & EXCEPTION_FLAG != 0 => 5,
+            _ => {
+                self.data_buffer.clear();
+                return Err(WitError::InvalidParameter("Invalid function code".to_string()));
             }
+        };
 
-            // We have a complete frame, process it
-            let result = self.parse_response();
+        if self.data_buffer.len() < expected_length {
+            return Ok(None); // Wait for more data
+        }
+
+        // A late answer from another slave on the bus is not ours to report
+        if self.slave_address != BROADCAST_ADDRESS && self.data_buffer[0] != self.slave_address {
             self.data_buffer.clear();
-            return result.map(Some);
+            return Ok(None);
         }
 
-        Ok(None)
+        // We have a complete frame, process it
+        let result = match function {
+            FUNC_READ => self.parse_response().map(Some),
+            FUNC_WRITE => self.check_crc().map(|_| None),
+            _ => self.check_crc().and_then(|_| {
+                Err(WitError::ModbusException {
+                    function: function & !EXCEPTION_FLAG,
+                    code: self.data_buffer[2],
+                })
+            }),
+        };
+        self.data_buffer.clear();
+        result
+    }
+
+    /// Verify the CRC of the complete frame in the buffer
+    fn check_crc(&self) -> WitResult<()> {
+        let (body, crc) = self.data_buffer.split_at(self.data_buffer.len() - 2);
+        if u16::from_le_bytes([crc[0], crc[1]]) != MODBUS_CRC.checksum(body) {
+            return Err(WitError::CrcMismatch);
+        }
+        Ok(())
     }
 
     /// Parse a complete Modbus response
@@ -95,7 +152,7 @@ impl ModbusProtocol {
         }
 
         // Check function code
-        if self.data_buffer[1] != 0x03 {
+        if self.data_buffer[1] != FUNC_READ {
             return Err(WitError::InvalidParameter("Invalid function code".to_string()));
         }
 
@@ -106,17 +163,7 @@ impl ModbusProtocol {
             return Err(WitError::InvalidParameter("Invalid frame length".to_string()));
         }
 
-        // Verify CRC
-        let received_crc = u16::from_le_bytes([
-            self.data_buffer[expected_frame_length - 2],
-            self.data_buffer[expected_frame_length - 1],
-        ]);
-        
-        let calculated_crc = MODBUS_CRC.checksum(&self.data_buffer[0..expected_frame_length - 2]);
-        
-        if received_crc != calculated_crc {
-            return Err(WitError::CrcMismatch);
-        }
+        self.check_crc()?;
 
         // Extract register values
         let mut registers = Vec::new();
@@ -203,3 +250,56 @@ pub fn parse_response(frame: &[u8]) -> WitResult<Vec<u16>> {
 
     Ok(registers)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::RegisterBlock;
+
+    fn feed(protocol: &mut ModbusProtocol, frame: &[u8]) -> Vec<WitResult<Option<RegisterBlock>>> {
+        frame.iter().map(|&byte| protocol.process_byte(byte)).collect()
+    }
+
+    #[test]
+    fn test_write_echo_and_exception() {
+        let mut protocol = ModbusProtocol::new(0x50);
+
+        // The echo of a write is consumed without an error
+        let echo = protocol.generate_write_request(crate::registers::BANDWIDTH, 4);
+        assert!(feed(&mut protocol, &echo).iter().all(|result| matches!(result, Ok(None))));
+
+        let mut exception = vec![0x50, 0x83, 0x02];
+        exception.extend_from_slice(&MODBUS_CRC.checksum(&exception).to_le_bytes());
+        let results = feed(&mut protocol, &exception);
+        assert!(matches!(
+            results.last(),
+            Some(Err(WitError::ModbusException { function: 0x03, code: 0x02 }))
+        ));
+
+        // The next read response is parsed normally
+        protocol.generate_read_request(crate::registers::BANDWIDTH, 1);
+        let mut response = vec![0x50, 0x03, 0x02, 0x00, 0x04];
+        response.extend_from_slice(&MODBUS_CRC.checksum(&response).to_le_bytes());
+        let results = feed(&mut protocol, &response);
+        assert_eq!(results.last().unwrap().as_ref().unwrap(), &Some((crate::registers::BANDWIDTH, vec![4])));
+    }
+
+    #[test]
+    fn test_answers_from_other_slaves_are_dropped() {
+        let mut response = vec![0x51, 0x03, 0x02, 0x00, 0x04];
+        response.extend_from_slice(&MODBUS_CRC.checksum(&response).to_le_bytes());
+        let mut exception = vec![0x51, 0x83, 0x02];
+        exception.extend_from_slice(&MODBUS_CRC.checksum(&exception).to_le_bytes());
+
+        let mut protocol = ModbusProtocol::new(0x50);
+        protocol.generate_read_request(crate::registers::BANDWIDTH, 1);
+        assert!(feed(&mut protocol, &response).iter().all(|result| matches!(result, Ok(None))));
+        assert!(feed(&mut protocol, &exception).iter().all(|result| matches!(result, Ok(None))));
+
+        // Requests sent to broadcast accept whichever slave answers
+        let mut protocol = ModbusProtocol::new(BROADCAST_ADDRESS);
+        protocol.generate_read_request(crate::registers::BANDWIDTH, 1);
+        let results = feed(&mut protocol, &response);
+        assert_eq!(results.last().unwrap().as_ref().unwrap(), &Some((crate::registers::BANDWIDTH, vec![4])));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::error::WitError;
+use std::{fmt, str::FromStr};
+
+/// WitMotion sensor families the crate can tell apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensorModel {
+    /// WT901C485 and other RS485 sensors with 16-bit angle registers
+    Wt901C485,
+    /// HWT905 and other WT905x sensors with 32-bit angle registers
+    Hwt905,
+    /// WT901 family streaming the normal protocol (TTL/RS232)
+    Wt901,
+    /// WT901BC and other WT901 variants with a barometer and GPS input
+    Wt901Bc,
+    /// JY61 6-axis sensors without magnetometer or register access
+    Jy61,
+}
+
+impl SensorModel {
+    /// All known models
+    pub const ALL: &'static [SensorModel] = &[
+        SensorModel::Wt901C485,
+        SensorModel::Hwt905,
+        SensorModel::Wt901,
+        SensorModel::Wt901Bc,
+        SensorModel::Jy61,
+    ];
+
+    /// Model name as printed on the device
+    pub fn name(&self) -> &'static str {
+        match self {
+            SensorModel::Wt901C485 => "WT901C485",
+            SensorModel::Hwt905 => "HWT905",
+            SensorModel::Wt901 => "WT901",
+            SensorModel::Wt901Bc => "WT901BC",
+            SensorModel::Jy61 => "JY61",
+        }
+    }
+}
+
+impl fmt::Display for SensorModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for SensorModel {
+    type Err = WitError;
+
+    /// Parse a model name, ignoring case ("hwt905", "WT901C485")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|model| model.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| WitError::InvalidParameter(format!("Unknown sensor model: {}", s)))
+    }
+}
@@ -0,0 +1,217 @@
+use crate::error::WitResult;
+use crate::protocol::RegisterBlock;
+use crate::registers::*;
+use std::collections::VecDeque;
+
+/// Length of a WIT normal protocol packet (header + type + 8 data bytes + checksum)
+pub const PACKET_LENGTH: usize = 11;
+
+/// First byte of every packet sent by the sensor
+pub const PACKET_HEADER: u8 = 0x55;
+
+/// Number of registers returned by a single register read (0x5F packet)
+pub const REGISTERS_PER_READ: u16 = 4;
+
+/// Leading bytes of every command sent to the sensor
+pub(crate) const COMMAND_HEADER: [u8; 2] = [0xFF, 0xAA];
+
+/// WIT normal protocol handler for WitMotion sensors
+///
+/// Decodes the 11-byte `0x55 0x5X ... sum` packets that TTL/RS232 sensors
+/// stream on their own and encodes `0xFF 0xAA reg lo hi` commands.
+pub struct NormalProtocol {
+    data_buffer: Vec<u8>,
+    pending_reads: VecDeque<u16>,
+}
+
+impl Default for NormalProtocol {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NormalProtocol {
+    /// Create a new normal protocol handler
+    pub fn new() -> Self {
+        Self {
+            data_buffer: Vec::with_capacity(256),
+            pending_reads: VecDeque::new(),
+        }
+    }
+
+    /// Generate a register read request
+    /// The sensor answers with a 0x5F packet holding four registers from `start_register`
+    pub fn generate_read_request(&mut self, start_register: u16) -> Vec<u8> {
+        self.pending_reads.push_back(start_register);
+        encode_command(READADDR as u8, start_register)
+    }
+
+    /// Generate a register write request
+    pub fn generate_write_request(&self, register: u16, value: u16) -> Vec<u8> {
+        encode_command(register as u8, value)
+    }
+
+    /// Process incoming byte and return parsed register data if a complete packet was received
+    ///
+    /// Some packets carry registers that are not contiguous (e.g. the acceleration
+    /// packet also holds TEMP), so a packet may yield more than one register block.
+    /// Bytes that do not start a valid packet are dropped to resynchronise on the
+    /// next 0x55 header, like the C SDK does.
+    pub fn process_byte(&mut self, byte: u8) -> WitResult<Option<Vec<RegisterBlock>>> {
+        self.data_buffer.push(byte);
+
+        if self.data_buffer[0] != PACKET_HEADER {
+            self.data_buffer.remove(0);
+            return Ok(None);
+        }
+
+        if self.data_buffer.len() < PACKET_LENGTH {
+            return Ok(None);
+        }
+
+        let checksum = packet_checksum(&self.data_buffer[..PACKET_LENGTH - 1]);
+        if checksum != self.data_buffer[PACKET_LENGTH - 1] {
+            self.data_buffer.remove(0);
+            return Ok(None);
+        }
+
+        let packet_type = self.data_buffer[1];
+        let mut values = [0i16; 4];
+        for (i, value) in values.iter_mut().enumerate() {
+            let offset = 2 + i * 2;
+            *value = i16::from_le_bytes([
+                self.data_buffer[offset],
+                self.data_buffer[offset + 1],
+            ]);
+        }
+        self.data_buffer.clear();
+
+        Ok(self.map_packet(packet_type, &values))
+    }
+
+    /// Map the four values of a packet onto the register blocks they belong to
+    fn map_packet(&mut self, packet_type: u8, values: &[i16; 4]) -> Option<Vec<RegisterBlock>> {
+        let start_register = match packet_type {
+            // Unsolicited register packets are dropped, we cannot tell where they belong
+            WIT_REGVALUE => self.pending_reads.pop_front()?,
+            _ => packet_register(packet_type)?,
+        };
+
+        let blocks = match packet_type {
+            WIT_ACC => vec![(start_register, values[..3].to_vec()), (TEMP, vec![values[3]])],
+            WIT_ANGLE => vec![(start_register, values[..3].to_vec()), (VERSION, vec![values[3]])],
+            WIT_GYRO | WIT_MAGNETIC => vec![(start_register, values[..3].to_vec())],
+            _ => vec![(start_register, values.to_vec())],
+        };
+
+        Some(blocks)
+    }
+
+    /// Clear the internal data buffer and forget pending register reads
+    pub fn clear_buffer(&mut self) {
+        self.data_buffer.clear();
+        self.pending_reads.clear();
+    }
+
+    /// Check if buffer should be reset (too much data accumulated)
+    pub fn should_reset_buffer(&self) -> bool {
+        self.data_buffer.len() > 256
+    }
+}
+
+/// Get the first register filled by an output packet type
+/// Register value packets (0x5F) depend on the read request and are not covered
+pub fn packet_register(packet_type: u8) -> Option<u16> {
+    match packet_type {
+        WIT_TIME => Some(YYMM),
+        WIT_ACC => Some(AX),
+        WIT_GYRO => Some(GX),
+        WIT_ANGLE => Some(ROLL),
+        WIT_MAGNETIC => Some(HX),
+        WIT_DPORT => Some(D0STATUS),
+        WIT_PRESS => Some(PRESSUREL),
+        WIT_GPS => Some(LONL),
+        WIT_VELOCITY => Some(GPSHEIGHT),
+        WIT_QUATER => Some(Q0),
+        WIT_GSA => Some(SVNUM),
+        _ => None,
+    }
+}
+
+/// Encode a `0xFF 0xAA reg lo hi` command
+pub(crate) fn encode_command(register: u8, value: u16) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(5);
+    frame.extend_from_slice(&COMMAND_HEADER);
+    frame.push(register);
+    frame.extend_from_slice(&value.to_le_bytes());
+    frame
+}
+
+/// Calculate the additive checksum used by normal protocol packets
+pub fn packet_checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(protocol: &mut NormalProtocol, bytes: &[u8]) -> Vec<RegisterBlock> {
+        let mut blocks = Vec::new();
+        for &byte in bytes {
+            if let Some(mut parsed) = protocol.process_byte(byte).unwrap() {
+                blocks.append(&mut parsed);
+            }
+        }
+        blocks
+    }
+
+    fn packet(packet_type: u8, values: [i16; 4]) -> Vec<u8> {
+        let mut bytes = vec![PACKET_HEADER, packet_type];
+        for value in values {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.push(packet_checksum(&bytes));
+        bytes
+    }
+
+    #[test]
+    fn test_acceleration_packet() {
+        let mut protocol = NormalProtocol::new();
+        let blocks = feed(&mut protocol, &packet(WIT_ACC, [100, -200, 2048, 3125]));
+
+        assert_eq!(blocks, vec![(AX, vec![100, -200, 2048]), (TEMP, vec![3125])]);
+    }
+
+    #[test]
+    fn test_resync_after_garbage() {
+        let mut protocol = NormalProtocol::new();
+        let mut bytes = vec![0x00, 0x55, 0x12];
+        bytes.extend(packet(WIT_GYRO, [1, 2, 3, 0]));
+        let mut corrupted = packet(WIT_ANGLE, [4, 5, 6, 7]);
+        corrupted[10] ^= 0xFF;
+        bytes.extend(corrupted);
+        bytes.extend(packet(WIT_MAGNETIC, [7, 8, 9, 0]));
+
+        let blocks = feed(&mut protocol, &bytes);
+        assert_eq!(blocks, vec![(GX, vec![1, 2, 3]), (HX, vec![7, 8, 9])]);
+    }
+
+    #[test]
+    fn test_register_read() {
+        let mut protocol = NormalProtocol::new();
+        assert_eq!(protocol.generate_read_request(BANDWIDTH), vec![0xFF, 0xAA, 0x27, 0x1F, 0x00]);
+
+        let blocks = feed(&mut protocol, &packet(WIT_REGVALUE, [4, 0, 3, 0]));
+        assert_eq!(blocks, vec![(BANDWIDTH, vec![4, 0, 3, 0])]);
+
+        // No read outstanding any more
+        assert!(feed(&mut protocol, &packet(WIT_REGVALUE, [4, 0, 3, 0])).is_empty());
+    }
+
+    #[test]
+    fn test_write_request() {
+        let protocol = NormalProtocol::new();
+        assert_eq!(protocol.generate_write_request(0x69, 0xB588), vec![0xFF, 0xAA, 0x69, 0x88, 0xB5]);
+    }
+}
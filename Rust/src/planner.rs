@@ -0,0 +1,236 @@
+use crate::{
+    error::{WitError, WitResult},
+    modbus::MAX_READ_COUNT,
+    registers::REGSIZE,
+    sensor::{DataUpdateFlags, SensorData, WitSensor, REGISTER_QUERY_TIMEOUT},
+    transport::Transport,
+};
+use std::time::{Duration, Instant};
+
+/// Time a read costs beyond its bytes on the wire: sensor processing and adapter latency
+pub const DEFAULT_ROUND_TRIP_LATENCY: Duration = Duration::from_millis(5);
+
+/// Rate assumed for transports that are not paced by a UART
+const DEFAULT_PLANNER_BAUD: u32 = 115200;
+
+/// Bytes of a Modbus read request plus the header and CRC of its response
+pub const MODBUS_READ_OVERHEAD: usize = 8 + 5;
+
+/// Plans the reads covering a set of registers
+///
+/// Every request costs a round trip, while spanning a gap costs two bytes per
+/// unwanted register. Links that answer only a few registers per request,
+/// like the WIT normal protocol, need several requests for a longer read.
+/// The planner picks the split with the lowest total time at the given baud
+/// rate, never exceeding 125 registers per read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadPlanner {
+    /// Time on the wire per byte, in nanoseconds
+    byte_time: u64,
+    /// Fixed cost of a request, in nanoseconds
+    latency: u64,
+    /// Most registers answered by one request
+    registers_per_request: u16,
+    /// Bytes of a request and its answer beyond the register values
+    request_overhead: u64,
+}
+
+impl ReadPlanner {
+    /// Plan reads for a link at the given baud rate
+    pub fn new(baud_rate: u32) -> Self {
+        Self::with_latency(baud_rate, DEFAULT_ROUND_TRIP_LATENCY)
+    }
+
+    /// Plan reads with a measured round trip latency
+    pub fn with_latency(baud_rate: u32, latency: Duration) -> Self {
+        Self {
+            // 10 bits per byte (start + 8 data + stop)
+            byte_time: 10_000_000_000 / baud_rate.max(1) as u64,
+            latency: latency.as_nanos() as u64,
+            registers_per_request: MAX_READ_COUNT,
+            request_overhead: MODBUS_READ_OVERHEAD as u64,
+        }
+    }
+
+    /// Plan for a link answering at most `registers_per_request` registers per
+    /// request, each request costing `overhead` bytes beyond the register values
+    pub fn with_requests(mut self, registers_per_request: u16, overhead: usize) -> Self {
+        self.registers_per_request = registers_per_request.max(1);
+        self.request_overhead = overhead as u64;
+        self
+    }
+
+    /// Split registers into the cheapest list of (start register, count) reads
+    pub fn plan(&self, registers: &[u16]) -> Vec<(u16, u16)> {
+        let mut registers = registers.to_vec();
+        registers.sort_unstable();
+        registers.dedup();
+        let n = registers.len();
+
+        // best[i]: cost of reading the first i registers, from[i]: where its last read starts
+        let mut best = vec![u64::MAX; n + 1];
+        let mut from = vec![0; n + 1];
+        best[0] = 0;
+        for end in 1..=n {
+            for start in 0..end {
+                let count = registers[end - 1] - registers[start] + 1;
+                if count > MAX_READ_COUNT {
+                    continue;
+                }
+                let cost = best[start] + self.read_cost(count);
+                // Ties keep the earliest start, which means fewer reads
+                if cost < best[end] {
+                    best[end] = cost;
+                    from[end] = start;
+                }
+            }
+        }
+
+        let mut reads = Vec::new();
+        let mut end = n;
+        while end > 0 {
+            let start = from[end];
+            reads.push((registers[start], registers[end - 1] - registers[start] + 1));
+            end = start;
+        }
+        reads.reverse();
+        reads
+    }
+
+    /// Estimated time for a list of reads
+    pub fn cost(&self, reads: &[(u16, u16)]) -> Duration {
+        Duration::from_nanos(reads.iter().map(|&(_, count)| self.read_cost(count)).sum())
+    }
+
+    fn read_cost(&self, count: u16) -> u64 {
+        let requests = count.div_ceil(self.registers_per_request) as u64;
+        requests * (self.latency + self.request_overhead * self.byte_time) + 2 * count as u64 * self.byte_time
+    }
+}
+
+impl<T: Transport> WitSensor<T> {
+    /// Get a read planner for the current link
+    pub fn read_planner(&self) -> ReadPlanner {
+        let transport = self.transport();
+        ReadPlanner::new(transport.baud_rate().unwrap_or(DEFAULT_PLANNER_BAUD))
+            .with_requests(transport.registers_per_request(), transport.request_overhead())
+    }
+
+    /// Read any set of registers with as little bus time as possible
+    pub fn read_register_set(&mut self, registers: &[u16]) -> WitResult<SensorData> {
+        if let Some(register) = registers.iter().find(|&&r| r as usize >= REGSIZE) {
+            return Err(WitError::InvalidParameter(format!("Invalid register: 0x{:02X}", register)));
+        }
+
+        let reads = self.read_planner().plan(registers);
+        self.read_blocks(&reads)
+    }
+
+    /// Read registers and return their fresh values in the order given
+    pub fn read_register_values(&mut self, registers: &[u16]) -> WitResult<Vec<u16>> {
+        if let Some(register) = registers.iter().find(|&&r| r as usize >= REGSIZE) {
+            return Err(WitError::InvalidParameter(format!("Invalid register: 0x{:02X}", register)));
+        }
+
+        let reads = self.read_planner().plan(registers);
+        self.read_fresh(&reads, registers)?;
+
+        registers
+            .iter()
+            .map(|&register| self.get_register(register).map(|value| value as u16).ok_or(WitError::Timeout))
+            .collect()
+    }
+
+    /// Send reads one at a time, waiting until every register they cover is fresh
+    pub(crate) fn read_blocks(&mut self, reads: &[(u16, u16)]) -> WitResult<SensorData> {
+        let registers: Vec<u16> = reads.iter().flat_map(|&(start, count)| start..start + count).collect();
+        self.read_fresh(reads, &registers)
+    }
+
+    /// Send reads one at a time, waiting for fresh values of the wanted registers
+    /// A half-duplex bus cannot queue requests
+    fn read_fresh(&mut self, reads: &[(u16, u16)], registers: &[u16]) -> WitResult<SensorData> {
+        // Forget cached values so only fresh answers count
+        for register in registers {
+            self.registers_mut().remove(register);
+        }
+
+        let mut update_flags = DataUpdateFlags::empty();
+        let mut data = None;
+        for &(start, count) in reads {
+            self.read_registers(start, count)?;
+
+            // Links with few registers per request answer in several blocks, wait for all of them
+            let deadline = Instant::now() + REGISTER_QUERY_TIMEOUT;
+            let wanted = || registers.iter().filter(|&&r| r >= start && r < start + count);
+            while wanted().any(|&register| self.get_register(register).is_none()) {
+                if Instant::now() >= deadline {
+                    return Err(WitError::Timeout);
+                }
+                if let Some(received) = self.process_next_block()? {
+                    update_flags |= received.update_flags;
+                    data = Some(received);
+                }
+            }
+        }
+
+        let mut data = data.unwrap_or_default();
+        data.update_flags = update_flags;
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockTransport, protocol::WitProtocol, registers::*};
+
+    #[test]
+    fn test_plan_depends_on_baud_rate() {
+        let registers = [AX, AY, AZ, Q0, Q1, Q2, Q3, TEMP];
+
+        // At 115200 a round trip is worth far more than the gaps, one read wins
+        let fast = ReadPlanner::new(115200);
+        assert_eq!(fast.plan(&registers), [(AX, Q3 - AX + 1)]);
+
+        // Without latency, gaps longer than a request and response header are skipped
+        let slow = ReadPlanner::with_latency(9600, Duration::ZERO);
+        assert_eq!(slow.plan(&registers), [(AX, 3), (TEMP, 1), (Q0, 4)]);
+        assert!(slow.cost(&slow.plan(&registers)) < slow.cost(&[(AX, Q3 - AX + 1)]));
+
+        // Never more than 125 registers per read
+        let reads = fast.plan(&[0x00, 0x8F]);
+        assert_eq!(reads, [(0x00, 1), (0x8F, 1)]);
+        assert!(fast.plan(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_requests_per_read_change_the_plan() {
+        let registers = [AX, GX, HX, TEMP];
+        assert_eq!(ReadPlanner::new(115200).plan(&registers), [(AX, TEMP - AX + 1)]);
+
+        // With four registers per request, spanning a gap no longer saves a round trip
+        let normal = ReadPlanner::new(115200).with_requests(4, 8);
+        assert_eq!(normal.plan(&registers), [(AX, 1), (GX, 4), (TEMP, 1)]);
+        assert!(normal.cost(&[(AX, TEMP - AX + 1)]) > normal.cost(&normal.plan(&registers)));
+    }
+
+    #[test]
+    fn test_reads_wait_for_every_request() {
+        let mut transport = MockTransport::new(WitProtocol::Normal).with(VERSION, 0x1234);
+        transport.registers_per_request = Some(4);
+        for (i, register) in (AX..=TEMP).enumerate() {
+            transport.registers.insert(register, i as u16 + 1);
+        }
+        let mut sensor = WitSensor::with_transport(transport);
+
+        // AX..TEMP needs four requests, all of their answers belong to this read
+        let data = sensor.read_blocks(&[(AX, TEMP - AX + 1)]).unwrap();
+        assert!(data.update_flags.contains(DataUpdateFlags::ACC | DataUpdateFlags::GYRO | DataUpdateFlags::ANGLE));
+        assert_eq!(sensor.get_register(TEMP), Some(13));
+
+        // No answer is left over to be taken for the next read
+        assert_eq!(sensor.read_register_values(&[VERSION, AZ]).unwrap(), [0x1234, 3]);
+        assert_eq!(sensor.transport_mut().receive().unwrap(), None);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{
+    error::{WitError, WitResult},
+    modbus::MAX_READ_COUNT,
+    registers::*,
+    sensor::{SensorData, WitSensor},
+    transport::Transport,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Unwanted registers a read may span to join two ranges
+pub const DEFAULT_MERGE_GAP: u16 = 4;
+
+/// A set of registers polled at their own rate
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollGroup {
+    registers: Vec<u16>,
+    interval: Duration,
+}
+
+impl PollGroup {
+    /// Poll the given registers once per interval
+    pub fn new(registers: &[u16], interval: Duration) -> WitResult<Self> {
+        if registers.is_empty() {
+            return Err(WitError::InvalidParameter("A poll group needs at least one register".to_string()));
+        }
+        if let Some(register) = registers.iter().find(|&&r| r as usize >= REGSIZE) {
+            return Err(WitError::InvalidParameter(format!("Invalid register: 0x{:02X}", register)));
+        }
+        if interval.is_zero() {
+            return Err(WitError::InvalidParameter("Poll interval must not be zero".to_string()));
+        }
+
+        let mut registers = registers.to_vec();
+        registers.sort_unstable();
+        registers.dedup();
+        Ok(Self { registers, interval })
+    }
+
+    /// Poll the given registers at a rate in Hz
+    pub fn at_rate(registers: &[u16], hz: f32) -> WitResult<Self> {
+        if !(hz > 0.0 && hz.is_finite()) {
+            return Err(WitError::InvalidParameter(format!("Invalid poll rate: {} Hz", hz)));
+        }
+        Self::new(registers, Duration::from_secs_f32(1.0 / hz))
+    }
+
+    /// Poll the registers from `first` to `last` inclusive at a rate in Hz
+    pub fn range(first: u16, last: u16, hz: f32) -> WitResult<Self> {
+        Self::at_rate(&(first..=last).collect::<Vec<_>>(), hz)
+    }
+
+    /// Get the registers in ascending order
+    pub fn registers(&self) -> &[u16] {
+        &self.registers
+    }
+
+    /// Get the time between two polls
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+/// Decides which registers are due and how to read them
+///
+/// Registers of all due groups are read together, with ranges closer than the
+/// merge gap joined into one read of at most 125 registers.
+#[derive(Debug, Clone)]
+pub struct PollScheduler {
+    groups: Vec<(PollGroup, Instant)>,
+    merge_gap: u16,
+}
+
+impl Default for PollScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PollScheduler {
+    /// Create a scheduler without groups
+    pub fn new() -> Self {
+        Self {
+            groups: Vec::new(),
+            merge_gap: DEFAULT_MERGE_GAP,
+        }
+    }
+
+    /// Add a group, first due immediately
+    pub fn add_group(&mut self, group: PollGroup) {
+        self.groups.push((group, Instant::now()));
+    }
+
+    /// Set how many unwanted registers a read may span to join two ranges
+    pub fn set_merge_gap(&mut self, gap: u16) {
+        self.merge_gap = gap;
+    }
+
+    /// Get the groups in the order they were added
+    pub fn groups(&self) -> impl Iterator<Item = &PollGroup> {
+        self.groups.iter().map(|(group, _)| group)
+    }
+
+    /// When the next group is due, None without groups
+    pub fn next_due(&self) -> Option<Instant> {
+        self.groups.iter().map(|(_, due)| *due).min()
+    }
+
+    /// Take the registers due at `now` in ascending order
+    /// Each due group is rescheduled one interval later
+    pub fn due_registers(&mut self, now: Instant) -> Vec<u16> {
+        let mut registers = Vec::new();
+        for (group, due) in &mut self.groups {
+            if *due > now {
+                continue;
+            }
+            registers.extend_from_slice(group.registers());
+
+            // Keep the rate steady, but skip polls that were missed entirely
+            *due += group.interval;
+            if *due <= now {
+                *due = now + group.interval;
+            }
+        }
+
+        registers.sort_unstable();
+        registers.dedup();
+        registers
+    }
+
+    /// Take the reads due at `now` as (start register, count) pairs
+    /// Ranges are joined across the merge gap
+    pub fn due_reads(&mut self, now: Instant) -> Vec<(u16, u16)> {
+        let registers = self.due_registers(now);
+        merge_ranges(&registers, self.merge_gap)
+    }
+}
+
+/// Join registers into reads, spanning gaps of up to `gap` registers
+fn merge_ranges(registers: &[u16], gap: u16) -> Vec<(u16, u16)> {
+    let mut registers = registers.to_vec();
+    registers.sort_unstable();
+    registers.dedup();
+
+    let mut reads: Vec<(u16, u16)> = Vec::new();
+    for register in registers {
+        match reads.last_mut() {
+            // Sorted and deduplicated, so `register` lies past the end of the last read
+            Some((start, count))
+                if register - (*start + *count) <= gap && register - *start < MAX_READ_COUNT =>
+            {
+                *count = register - *start + 1;
+            }
+            _ => reads.push((register, 1)),
+        }
+    }
+
+    reads
+}
+
+impl<T: Transport> WitSensor<T> {
+    /// Wait for the next due poll group, read it and return the updated data
+    pub fn poll(&mut self, scheduler: &mut PollScheduler) -> WitResult<SensorData> {
+        let due = scheduler
+            .next_due()
+            .ok_or_else(|| WitError::InvalidParameter("No poll groups defined".to_string()))?;
+        let now = Instant::now();
+        if due > now {
+            thread::sleep(due - now);
+        }
+
+        let reads = scheduler.due_reads(Instant::now());
+        self.read_blocks(&reads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_merge_and_keep_rates() {
+        let mut scheduler = PollScheduler::new();
+        scheduler.add_group(PollGroup::range(ROLL, YAW, 100.0).unwrap());
+        scheduler.add_group(PollGroup::range(HX, HZ, 10.0).unwrap());
+        scheduler.add_group(PollGroup::at_rate(&[TEMP, VERSION, BATVAL], 0.1).unwrap());
+
+        let start = Instant::now();
+        assert_eq!(
+            scheduler.due_registers(start),
+            [VERSION, HX, HY, HZ, ROLL, PITCH, YAW, TEMP, BATVAL]
+        );
+        assert_eq!(scheduler.due_registers(start + Duration::from_millis(10)), [ROLL, PITCH, YAW]);
+        assert_eq!(
+            scheduler.due_registers(start + Duration::from_millis(100)),
+            [HX, HY, HZ, ROLL, PITCH, YAW]
+        );
+        assert!(scheduler.due_registers(start + Duration::from_millis(105)).is_empty());
+
+        // HX..HZ and ROLL..TEMP are one read, VERSION and BATVAL are too far away
+        let later = start + Duration::from_secs(10);
+        assert_eq!(scheduler.due_reads(later), [(VERSION, 1), (HX, 7), (BATVAL, 1)]);
+        scheduler.set_merge_gap(0);
+        assert_eq!(scheduler.due_reads(later + Duration::from_millis(101)), [(HX, 6)]);
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(merge_ranges(&[AZ, AX, AY, AX, HX], 2), [(AX, 3), (HX, 1)]);
+        assert_eq!(merge_ranges(&[HX, AX], 5), [(AX, 7)]);
+        // A read never exceeds the Modbus limit of 125 registers
+        assert_eq!(merge_ranges(&[0x00, 0x7F, 0x80, 0x8F], 200), [(0x00, 1), (0x7F, 17)]);
+        assert!(merge_ranges(&[], 4).is_empty());
+    }
+}
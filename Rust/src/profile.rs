@@ -0,0 +1,218 @@
+use crate::{
+    jy61::jy61_temperature,
+    model::SensorModel,
+    protocol::WitProtocol,
+    registers::*,
+    DEFAULT_READ_COUNT, DEFAULT_READ_COUNT_905X,
+};
+use bitflags::bitflags;
+
+bitflags! {
+    /// Features a sensor model provides
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct Capabilities: u16 {
+        const MAGNETOMETER = 0x01;          // HX..HZ and magnetometer configuration
+        const HIGH_PRECISION_ANGLES = 0x02; // 32-bit angles in LROLL..HYAW, TEMP905X
+        const PORT_STATUS = 0x04;           // D0STATUS..D3STATUS
+        const PRESSURE = 0x08;              // Barometric pressure and height
+        const GPS = 0x10;                   // Position, speed and accuracy from a GPS module
+        const QUATERNION = 0x20;            // Q0..Q3
+        const REGISTER_ACCESS = 0x40;       // Register reads and unlocked writes
+    }
+}
+
+/// How the temperature register is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TemperatureFormat {
+    /// Hundredths of a degree Celsius
+    Centidegrees,
+    /// Raw MPU6050 reading (JY61)
+    Mpu6050,
+}
+
+/// Register layout and capabilities of a sensor model
+///
+/// Several REG.h addresses mean different things depending on the model: on
+/// WT905x sensors ROLL..TEMP and D0STATUS..D2STATUS hold 32-bit angles and
+/// the temperature instead. The profile decides which meaning applies.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceProfile {
+    pub model: SensorModel,
+    pub capabilities: Capabilities,
+    pub temperature_format: TemperatureFormat,
+}
+
+impl DeviceProfile {
+    pub const WT901C485: DeviceProfile = DeviceProfile {
+        model: SensorModel::Wt901C485,
+        capabilities: Capabilities::MAGNETOMETER
+            .union(Capabilities::PORT_STATUS)
+            .union(Capabilities::QUATERNION)
+            .union(Capabilities::REGISTER_ACCESS),
+        temperature_format: TemperatureFormat::Centidegrees,
+    };
+
+    pub const HWT905: DeviceProfile = DeviceProfile {
+        model: SensorModel::Hwt905,
+        capabilities: Capabilities::MAGNETOMETER
+            .union(Capabilities::HIGH_PRECISION_ANGLES)
+            .union(Capabilities::QUATERNION)
+            .union(Capabilities::REGISTER_ACCESS),
+        temperature_format: TemperatureFormat::Centidegrees,
+    };
+
+    pub const WT901: DeviceProfile = DeviceProfile {
+        model: SensorModel::Wt901,
+        capabilities: Capabilities::MAGNETOMETER
+            .union(Capabilities::PORT_STATUS)
+            .union(Capabilities::QUATERNION)
+            .union(Capabilities::REGISTER_ACCESS),
+        temperature_format: TemperatureFormat::Centidegrees,
+    };
+
+    pub const WT901BC: DeviceProfile = DeviceProfile {
+        model: SensorModel::Wt901Bc,
+        capabilities: Capabilities::MAGNETOMETER
+            .union(Capabilities::PORT_STATUS)
+            .union(Capabilities::PRESSURE)
+            .union(Capabilities::GPS)
+            .union(Capabilities::QUATERNION)
+            .union(Capabilities::REGISTER_ACCESS),
+        temperature_format: TemperatureFormat::Centidegrees,
+    };
+
+    pub const JY61: DeviceProfile = DeviceProfile {
+        model: SensorModel::Jy61,
+        capabilities: Capabilities::empty(),
+        temperature_format: TemperatureFormat::Mpu6050,
+    };
+
+    /// Get the profile of a sensor model
+    pub fn for_model(model: SensorModel) -> &'static DeviceProfile {
+        match model {
+            SensorModel::Wt901C485 => &Self::WT901C485,
+            SensorModel::Hwt905 => &Self::HWT905,
+            SensorModel::Wt901 => &Self::WT901,
+            SensorModel::Wt901Bc => &Self::WT901BC,
+            SensorModel::Jy61 => &Self::JY61,
+        }
+    }
+
+    /// Get the most likely profile for a sensor speaking the given protocol
+    pub fn for_protocol(protocol: WitProtocol) -> &'static DeviceProfile {
+        match protocol {
+            WitProtocol::Modbus => &Self::WT901C485,
+            WitProtocol::Modbus905x | WitProtocol::Can905x => &Self::HWT905,
+            WitProtocol::Jy61 => &Self::JY61,
+            WitProtocol::Normal | WitProtocol::Can | WitProtocol::I2c => &Self::WT901,
+        }
+    }
+
+    /// Check whether the model provides all the given capabilities
+    pub fn has(&self, capabilities: Capabilities) -> bool {
+        self.capabilities.contains(capabilities)
+    }
+
+    /// Check whether a register exists with this meaning on the model
+    pub fn supports(&self, register: Register) -> bool {
+        use Register::*;
+
+        let high_precision = self.has(Capabilities::HIGH_PRECISION_ANGLES);
+        let required = match register {
+            Hx | Hy | Hz | HxOffset | HyOffset | HzOffset | MagRangX | MagRangY | MagRangZ
+            | MagFilt | MagSensor => Capabilities::MAGNETOMETER,
+            LRoll | HRoll | LPitch | HPitch | LYaw | HYaw | Temp905x => {
+                return high_precision;
+            }
+            Roll | Pitch | Yaw | Temp => return !high_precision,
+            // D0STATUS..D2STATUS hold LYAW, HYAW and TEMP905X on WT905x sensors
+            D0Status | D1Status | D2Status if high_precision => return false,
+            D0Status | D1Status | D2Status | D3Status => Capabilities::PORT_STATUS,
+            PressureL | PressureH | HeightL | HeightH => Capabilities::PRESSURE,
+            LonL | LonH | LatL | LatH | GpsHeight | GpsYaw | GpsVL | GpsVH | SvNum | Pdop
+            | Hdop | Vdop => Capabilities::GPS,
+            Q0 | Q1 | Q2 | Q3 => Capabilities::QUATERNION,
+            Ax | Ay | Az | Gx | Gy | Gz => Capabilities::empty(),
+            _ => Capabilities::REGISTER_ACCESS,
+        };
+        self.has(required)
+    }
+
+    /// Look up the meaning of an address on this model
+    pub fn resolve(&self, address: u16) -> Option<Register> {
+        Register::aliases(address)
+            .into_iter()
+            .find(|register| self.supports(*register))
+    }
+
+    /// Last angle register (YAW, or HYAW for 32-bit angles)
+    pub fn angle_end(&self) -> u16 {
+        if self.has(Capabilities::HIGH_PRECISION_ANGLES) {
+            HYAW
+        } else {
+            YAW
+        }
+    }
+
+    /// Temperature register (TEMP, or TEMP905X for 32-bit angles)
+    pub fn temperature_register(&self) -> u16 {
+        if self.has(Capabilities::HIGH_PRECISION_ANGLES) {
+            TEMP905X
+        } else {
+            TEMP
+        }
+    }
+
+    /// Last register of the measurement block starting at AX
+    pub fn data_end(&self) -> u16 {
+        if self.has(Capabilities::GPS) {
+            VDOP
+        } else if self.has(Capabilities::PRESSURE) {
+            HEIGHTH
+        } else {
+            self.temperature_register()
+        }
+    }
+
+    /// Number of registers from AX to poll for sensor data
+    /// Covers acceleration, rate and angles, plus pressure and GPS when present
+    pub fn read_count(&self) -> u16 {
+        if self.has(Capabilities::GPS) || self.has(Capabilities::PRESSURE) {
+            self.data_end() - AX + 1
+        } else if self.has(Capabilities::HIGH_PRECISION_ANGLES) {
+            DEFAULT_READ_COUNT_905X
+        } else {
+            DEFAULT_READ_COUNT
+        }
+    }
+
+    /// Convert a raw temperature reading to °C
+    pub fn temperature(&self, raw: i16) -> f32 {
+        match self.temperature_format {
+            TemperatureFormat::Centidegrees => raw as f32 / 100.0,
+            TemperatureFormat::Mpu6050 => jy61_temperature(raw),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliases_follow_profile() {
+        let wt901 = DeviceProfile::for_model(SensorModel::Wt901C485);
+        let hwt905 = DeviceProfile::for_model(SensorModel::Hwt905);
+
+        assert_eq!(wt901.resolve(0x3E), Some(Register::Pitch));
+        assert_eq!(hwt905.resolve(0x3E), Some(Register::HRoll));
+        assert_eq!(wt901.resolve(0x43), Some(Register::D2Status));
+        assert_eq!(hwt905.resolve(0x43), Some(Register::Temp905x));
+        assert_eq!(hwt905.resolve(0x44), None);
+
+        assert_eq!(DeviceProfile::JY61.resolve(HX), None);
+        assert_eq!(DeviceProfile::JY61.resolve(TEMP), Some(Register::Temp));
+        assert_eq!(DeviceProfile::WT901C485.resolve(LONL), None);
+        assert_eq!(DeviceProfile::WT901BC.resolve(LONL), Some(Register::LonL));
+    }
+}
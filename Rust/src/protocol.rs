@@ -0,0 +1,42 @@
+/// Wire protocols spoken by WitMotion sensors
+/// These correspond to the WIT_PROTOCOL_* definitions in wit_c_sdk.h
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WitProtocol {
+    /// 11-byte 0x55 packets streamed by TTL/RS232 sensors
+    Normal,
+    /// Modbus RTU over RS485
+    #[default]
+    Modbus,
+    /// Modbus RTU as spoken by the WT905x series (HWT905 and friends),
+    /// with 32-bit angles in LROLL..HYAW and temperature in TEMP905X
+    Modbus905x,
+    /// 8-byte CAN frames
+    Can,
+    /// 8-byte CAN frames as sent by the WT905x series, with 32-bit angles
+    Can905x,
+    /// Register reads and writes over I2C
+    I2c,
+    /// 0x55 packet stream of JY61 6-axis sensors, which take fixed
+    /// commands instead of register writes and have no magnetometer
+    Jy61,
+}
+
+impl WitProtocol {
+    /// Check whether the protocol uses Modbus RTU framing
+    pub fn is_modbus(&self) -> bool {
+        matches!(self, WitProtocol::Modbus | WitProtocol::Modbus905x)
+    }
+
+    /// Check whether the protocol uses CAN frames
+    pub fn is_can(&self) -> bool {
+        matches!(self, WitProtocol::Can | WitProtocol::Can905x)
+    }
+
+    /// Check whether the sensor reports 32-bit WT905x angles
+    pub fn is_905x(&self) -> bool {
+        matches!(self, WitProtocol::Modbus905x | WitProtocol::Can905x)
+    }
+}
+
+/// Consecutive register values decoded from a frame, starting at the given register
+pub type RegisterBlock = (u16, Vec<i16>);
@@ -1,5 +1,8 @@
-/// Register addresses for WitMotion sensors
-/// These correspond to the definitions in REG.h
+//! Register addresses for WitMotion sensors
+//! These correspond to the definitions in REG.h
+
+use crate::error::WitError;
+use std::{fmt, str::FromStr};
 
 // Control and configuration registers
 pub const SAVE: u16 = 0x00;
@@ -38,12 +41,27 @@ pub const D3PWMT: u16 = 0x19;
 // Device configuration
 pub const IICADDR: u16 = 0x1A;
 pub const LEDOFF: u16 = 0x1B;
+pub const MAGRANGX: u16 = 0x1C;
+pub const MAGRANGY: u16 = 0x1D;
+pub const MAGRANGZ: u16 = 0x1E;
 pub const BANDWIDTH: u16 = 0x1F;
 pub const GYRORANGE: u16 = 0x20;
 pub const ACCRANGE: u16 = 0x21;
 pub const SLEEP: u16 = 0x22;
 pub const ORIENT: u16 = 0x23;
 pub const AXIS6: u16 = 0x24;
+pub const FILTK: u16 = 0x25;
+pub const GPSBAUD: u16 = 0x26;
+pub const READADDR: u16 = 0x27;
+pub const BWSCALE: u16 = 0x28;
+pub const MOVETHR: u16 = 0x28;
+pub const MOVESTA: u16 = 0x29;
+pub const ACCFILT: u16 = 0x2A;
+pub const GYROFILT: u16 = 0x2B;
+pub const MAGFILT: u16 = 0x2C;
+pub const POWONSEND: u16 = 0x2D;
+pub const VERSION: u16 = 0x2E;
+pub const CCBW: u16 = 0x2F;
 
 // Timestamp registers
 pub const YYMM: u16 = 0x30;
@@ -90,6 +108,10 @@ pub const LONL: u16 = 0x49;
 pub const LONH: u16 = 0x4A;
 pub const LATL: u16 = 0x4B;
 pub const LATH: u16 = 0x4C;
+pub const GPSHEIGHT: u16 = 0x4D;
+pub const GPSYAW: u16 = 0x4E;
+pub const GPSVL: u16 = 0x4F;
+pub const GPSVH: u16 = 0x50;
 
 // Quaternion registers
 pub const Q0: u16 = 0x51;
@@ -97,6 +119,75 @@ pub const Q1: u16 = 0x52;
 pub const Q2: u16 = 0x53;
 pub const Q3: u16 = 0x54;
 
+// GPS accuracy registers
+pub const SVNUM: u16 = 0x55;
+pub const PDOP: u16 = 0x56;
+pub const HDOP: u16 = 0x57;
+pub const VDOP: u16 = 0x58;
+
+// Alarm, calibration and installation registers
+pub const DELAYT: u16 = 0x59;
+pub const XMIN: u16 = 0x5A;
+pub const XMAX: u16 = 0x5B;
+pub const BATVAL: u16 = 0x5C;
+pub const ALARMPIN: u16 = 0x5D;
+pub const YMIN: u16 = 0x5E;
+pub const YMAX: u16 = 0x5F;
+pub const GYROZSCALE: u16 = 0x60;
+pub const GYROCALITHR: u16 = 0x61;
+pub const ALARMLEVEL: u16 = 0x62;
+pub const GYROCALTIME: u16 = 0x63;
+pub const REFROLL: u16 = 0x64;
+pub const REFPITCH: u16 = 0x65;
+pub const REFYAW: u16 = 0x66;
+pub const GPSTYPE: u16 = 0x67;
+pub const TRIGTIME: u16 = 0x68;
+pub const KEY: u16 = 0x69;
+pub const WERROR: u16 = 0x6A;
+pub const TIMEZONE: u16 = 0x6B;
+pub const CALICNT: u16 = 0x6C;
+pub const WZCNT: u16 = 0x6D;
+pub const WZTIME: u16 = 0x6E;
+pub const WZSTATIC: u16 = 0x6F;
+pub const ACCSENSOR: u16 = 0x70;
+pub const GYROSENSOR: u16 = 0x71;
+pub const MAGSENSOR: u16 = 0x72;
+pub const PRESSENSOR: u16 = 0x73;
+pub const MODDELAY: u16 = 0x74;
+pub const ANGLEAXIS: u16 = 0x75;
+pub const XRSCALE: u16 = 0x76;
+pub const YRSCALE: u16 = 0x77;
+pub const ZRSCALE: u16 = 0x78;
+pub const XREFROLL: u16 = 0x79;
+pub const YREFPITCH: u16 = 0x7A;
+pub const ZREFYAW: u16 = 0x7B;
+pub const ANGXOFFSET: u16 = 0x7C;
+pub const ANGYOFFSET: u16 = 0x7D;
+pub const ANGZOFFSET: u16 = 0x7E;
+
+// Device identification registers
+pub const NUMBERID1: u16 = 0x7F;
+pub const NUMBERID2: u16 = 0x80;
+pub const NUMBERID3: u16 = 0x81;
+pub const NUMBERID4: u16 = 0x82;
+pub const NUMBERID5: u16 = 0x83;
+pub const NUMBERID6: u16 = 0x84;
+
+// Inclinometer scale registers
+pub const XA85PSCALE: u16 = 0x85;
+pub const XA85NSCALE: u16 = 0x86;
+pub const YA85PSCALE: u16 = 0x87;
+pub const YA85NSCALE: u16 = 0x88;
+pub const XA30PSCALE: u16 = 0x89;
+pub const XA30NSCALE: u16 = 0x8A;
+pub const YA30PSCALE: u16 = 0x8B;
+pub const YA30NSCALE: u16 = 0x8C;
+
+// Chip ID and initialization flag
+pub const CHIPIDL: u16 = 0x8D;
+pub const CHIPIDH: u16 = 0x8E;
+pub const REGINITFLAG: u16 = REGSIZE as u16 - 1;
+
 // Register size definition
 pub const REGSIZE: usize = 0x90;
 
@@ -124,6 +215,21 @@ pub const CALGYROACC: u16 = 0x01;
 pub const CALMAG: u16 = 0x02;
 pub const CALALTITUDE: u16 = 0x03;
 pub const CALANGLEZ: u16 = 0x04;
+pub const CALACCL: u16 = 0x05;
+pub const CALACCR: u16 = 0x06;
+pub const CALMAGMM: u16 = 0x07;
+pub const CALREFANGLE: u16 = 0x08;
+pub const CALMAG2STEP: u16 = 0x09;
+pub const CALHEXAHEDRON: u16 = 0x12;
+
+// Algorithm selection (AXIS6)
+pub const ALGRITHM9: u16 = 0;
+pub const ALGRITHM6: u16 = 1;
+
+// Save register commands
+pub const SAVE_PARAM: u16 = 0x00;
+pub const SAVE_DEFAULT: u16 = 0x01; // Restore factory defaults
+pub const SAVE_SWRST: u16 = 0xFF;
 
 // Baud rate constants
 pub const WIT_BAUD_4800: u16 = 1;
@@ -136,6 +242,40 @@ pub const WIT_BAUD_230400: u16 = 7;
 pub const WIT_BAUD_460800: u16 = 8;
 pub const WIT_BAUD_921600: u16 = 9;
 
+// CAN baud rate constants
+pub const CAN_BAUD_1000000: u16 = 0;
+pub const CAN_BAUD_800000: u16 = 1;
+pub const CAN_BAUD_500000: u16 = 2;
+pub const CAN_BAUD_400000: u16 = 3;
+pub const CAN_BAUD_250000: u16 = 4;
+pub const CAN_BAUD_200000: u16 = 5;
+pub const CAN_BAUD_125000: u16 = 6;
+pub const CAN_BAUD_100000: u16 = 7;
+pub const CAN_BAUD_80000: u16 = 8;
+pub const CAN_BAUD_50000: u16 = 9;
+pub const CAN_BAUD_40000: u16 = 10;
+pub const CAN_BAUD_20000: u16 = 11;
+pub const CAN_BAUD_10000: u16 = 12;
+pub const CAN_BAUD_5000: u16 = 13;
+pub const CAN_BAUD_3000: u16 = 14;
+
+// Output content bits (RSW)
+pub const RSW_TIME: u16 = 0x01;
+pub const RSW_ACC: u16 = 0x02;
+pub const RSW_GYRO: u16 = 0x04;
+pub const RSW_ANGLE: u16 = 0x08;
+pub const RSW_MAG: u16 = 0x10;
+pub const RSW_PORT: u16 = 0x20;
+pub const RSW_PRESS: u16 = 0x40;
+pub const RSW_GPS: u16 = 0x80;
+pub const RSW_V: u16 = 0x100;
+pub const RSW_Q: u16 = 0x200;
+pub const RSW_GSA: u16 = 0x400;
+pub const RSW_MASK: u16 = 0xfff;
+
+// Unlock key for configuration writes
+pub const KEY_UNLOCK: u16 = 0xB588;
+
 // Output rate constants
 pub const RRATE_NONE: u16 = 0x0D;
 pub const RRATE_02HZ: u16 = 0x01;
@@ -150,3 +290,346 @@ pub const RRATE_100HZ: u16 = 0x09;
 pub const RRATE_125HZ: u16 = 0x0A;
 pub const RRATE_200HZ: u16 = 0x0B;
 pub const RRATE_ONCE: u16 = 0x0C;
+
+/// How a register can be accessed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegisterAccess {
+    /// Measurements and identification, writes are ignored
+    ReadOnly,
+    /// Configuration that can be read back
+    ReadWrite,
+    /// Commands, reading them returns nothing useful
+    WriteOnly,
+}
+
+impl RegisterAccess {
+    /// Check whether the register can be read
+    pub fn is_readable(&self) -> bool {
+        !matches!(self, RegisterAccess::WriteOnly)
+    }
+
+    /// Check whether the register can be written
+    pub fn is_writable(&self) -> bool {
+        !matches!(self, RegisterAccess::ReadOnly)
+    }
+}
+
+// Shorthands for the register table below
+const RO: RegisterAccess = RegisterAccess::ReadOnly;
+const RW: RegisterAccess = RegisterAccess::ReadWrite;
+const WO: RegisterAccess = RegisterAccess::WriteOnly;
+const S: bool = true;
+const U: bool = false;
+
+// Full scale conversions of the default sensor ranges
+const ACC_LSB: f32 = 16.0 / 32768.0;
+const GYRO_LSB: f32 = 2000.0 / 32768.0;
+const ANGLE_LSB: f32 = 180.0 / 32768.0;
+const UNIT_LSB: f32 = 1.0 / 32768.0;
+
+/// Metadata describing a register
+struct RegisterInfo {
+    name: &'static str,
+    address: u16,
+    access: RegisterAccess,
+    signed: bool,
+    unit: Option<&'static str>,
+    scale: f32,
+}
+
+macro_rules! register_table {
+    ($($variant:ident => $name:literal, $address:expr, $access:expr, $signed:expr, $unit:expr, $scale:expr;)*) => {
+        /// Every register defined in REG.h
+        ///
+        /// Some addresses are shared: the WT905x angle pairs overlap ROLL..TEMP and
+        /// D0STATUS..D2STATUS, and BWSCALE/MOVETHR share 0x28. Lookups by address
+        /// return the standard WT901 meaning first.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum Register {
+            $($variant,)*
+        }
+
+        impl Register {
+            /// All registers in address order
+            pub const ALL: &'static [Register] = &[$(Register::$variant,)*];
+
+            fn info(&self) -> RegisterInfo {
+                match self {
+                    $(Register::$variant => RegisterInfo {
+                        name: $name,
+                        address: $address,
+                        access: $access,
+                        signed: $signed,
+                        unit: $unit,
+                        scale: $scale,
+                    },)*
+                }
+            }
+        }
+    };
+}
+
+register_table! {
+    Save => "SAVE", SAVE, WO, U, None, 1.0;
+    Calsw => "CALSW", CALSW, RW, U, None, 1.0;
+    Rsw => "RSW", RSW, RW, U, None, 1.0;
+    Rrate => "RRATE", RRATE, RW, U, None, 1.0;
+    Baud => "BAUD", BAUD, RW, U, None, 1.0;
+    AxOffset => "AXOFFSET", AXOFFSET, RW, S, None, 1.0;
+    AyOffset => "AYOFFSET", AYOFFSET, RW, S, None, 1.0;
+    AzOffset => "AZOFFSET", AZOFFSET, RW, S, None, 1.0;
+    GxOffset => "GXOFFSET", GXOFFSET, RW, S, None, 1.0;
+    GyOffset => "GYOFFSET", GYOFFSET, RW, S, None, 1.0;
+    GzOffset => "GZOFFSET", GZOFFSET, RW, S, None, 1.0;
+    HxOffset => "HXOFFSET", HXOFFSET, RW, S, None, 1.0;
+    HyOffset => "HYOFFSET", HYOFFSET, RW, S, None, 1.0;
+    HzOffset => "HZOFFSET", HZOFFSET, RW, S, None, 1.0;
+    D0Mode => "D0MODE", D0MODE, RW, U, None, 1.0;
+    D1Mode => "D1MODE", D1MODE, RW, U, None, 1.0;
+    D2Mode => "D2MODE", D2MODE, RW, U, None, 1.0;
+    D3Mode => "D3MODE", D3MODE, RW, U, None, 1.0;
+    D0PwmH => "D0PWMH", D0PWMH, RW, U, Some("us"), 1.0;
+    D1PwmH => "D1PWMH", D1PWMH, RW, U, Some("us"), 1.0;
+    D2PwmH => "D2PWMH", D2PWMH, RW, U, Some("us"), 1.0;
+    D3PwmH => "D3PWMH", D3PWMH, RW, U, Some("us"), 1.0;
+    D0PwmT => "D0PWMT", D0PWMT, RW, U, Some("us"), 1.0;
+    D1PwmT => "D1PWMT", D1PWMT, RW, U, Some("us"), 1.0;
+    D2PwmT => "D2PWMT", D2PWMT, RW, U, Some("us"), 1.0;
+    D3PwmT => "D3PWMT", D3PWMT, RW, U, Some("us"), 1.0;
+    IicAddr => "IICADDR", IICADDR, RW, U, None, 1.0;
+    LedOff => "LEDOFF", LEDOFF, RW, U, None, 1.0;
+    MagRangX => "MAGRANGX", MAGRANGX, RW, U, None, 1.0;
+    MagRangY => "MAGRANGY", MAGRANGY, RW, U, None, 1.0;
+    MagRangZ => "MAGRANGZ", MAGRANGZ, RW, U, None, 1.0;
+    Bandwidth => "BANDWIDTH", BANDWIDTH, RW, U, None, 1.0;
+    GyroRange => "GYRORANGE", GYRORANGE, RW, U, None, 1.0;
+    AccRange => "ACCRANGE", ACCRANGE, RW, U, None, 1.0;
+    Sleep => "SLEEP", SLEEP, RW, U, None, 1.0;
+    Orient => "ORIENT", ORIENT, RW, U, None, 1.0;
+    Axis6 => "AXIS6", AXIS6, RW, U, None, 1.0;
+    FiltK => "FILTK", FILTK, RW, U, None, 1.0;
+    GpsBaud => "GPSBAUD", GPSBAUD, RW, U, None, 1.0;
+    ReadAddr => "READADDR", READADDR, WO, U, None, 1.0;
+    BwScale => "BWSCALE", BWSCALE, RW, U, None, 1.0;
+    MoveThr => "MOVETHR", MOVETHR, RW, U, None, 1.0;
+    MoveSta => "MOVESTA", MOVESTA, RO, U, None, 1.0;
+    AccFilt => "ACCFILT", ACCFILT, RW, U, None, 1.0;
+    GyroFilt => "GYROFILT", GYROFILT, RW, U, None, 1.0;
+    MagFilt => "MAGFILT", MAGFILT, RW, U, None, 1.0;
+    PowOnSend => "POWONSEND", POWONSEND, RW, U, None, 1.0;
+    Version => "VERSION", VERSION, RO, U, None, 1.0;
+    Ccbw => "CCBW", CCBW, RW, U, None, 1.0;
+    Yymm => "YYMM", YYMM, RW, U, None, 1.0;
+    Ddhh => "DDHH", DDHH, RW, U, None, 1.0;
+    Mmss => "MMSS", MMSS, RW, U, None, 1.0;
+    Ms => "MS", MS, RW, U, Some("ms"), 1.0;
+    Ax => "AX", AX, RO, S, Some("g"), ACC_LSB;
+    Ay => "AY", AY, RO, S, Some("g"), ACC_LSB;
+    Az => "AZ", AZ, RO, S, Some("g"), ACC_LSB;
+    Gx => "GX", GX, RO, S, Some("°/s"), GYRO_LSB;
+    Gy => "GY", GY, RO, S, Some("°/s"), GYRO_LSB;
+    Gz => "GZ", GZ, RO, S, Some("°/s"), GYRO_LSB;
+    Hx => "HX", HX, RO, S, None, 1.0;
+    Hy => "HY", HY, RO, S, None, 1.0;
+    Hz => "HZ", HZ, RO, S, None, 1.0;
+    Roll => "ROLL", ROLL, RO, S, Some("°"), ANGLE_LSB;
+    Pitch => "PITCH", PITCH, RO, S, Some("°"), ANGLE_LSB;
+    Yaw => "YAW", YAW, RO, S, Some("°"), ANGLE_LSB;
+    Temp => "TEMP", TEMP, RO, S, Some("°C"), 0.01;
+    LRoll => "LROLL", LROLL, RO, U, None, 1.0;
+    HRoll => "HROLL", HROLL, RO, S, None, 1.0;
+    LPitch => "LPITCH", LPITCH, RO, U, None, 1.0;
+    HPitch => "HPITCH", HPITCH, RO, S, None, 1.0;
+    LYaw => "LYAW", LYAW, RO, U, None, 1.0;
+    HYaw => "HYAW", HYAW, RO, S, None, 1.0;
+    Temp905x => "TEMP905X", TEMP905X, RO, S, Some("°C"), 0.01;
+    D0Status => "D0STATUS", D0STATUS, RO, U, None, 1.0;
+    D1Status => "D1STATUS", D1STATUS, RO, U, None, 1.0;
+    D2Status => "D2STATUS", D2STATUS, RO, U, None, 1.0;
+    D3Status => "D3STATUS", D3STATUS, RO, U, None, 1.0;
+    PressureL => "PRESSUREL", PRESSUREL, RO, U, None, 1.0;
+    PressureH => "PRESSUREH", PRESSUREH, RO, S, None, 1.0;
+    HeightL => "HEIGHTL", HEIGHTL, RO, U, None, 1.0;
+    HeightH => "HEIGHTH", HEIGHTH, RO, S, None, 1.0;
+    LonL => "LONL", LONL, RO, U, None, 1.0;
+    LonH => "LONH", LONH, RO, S, None, 1.0;
+    LatL => "LATL", LATL, RO, U, None, 1.0;
+    LatH => "LATH", LATH, RO, S, None, 1.0;
+    GpsHeight => "GPSHEIGHT", GPSHEIGHT, RO, S, Some("m"), 0.1;
+    GpsYaw => "GPSYAW", GPSYAW, RO, U, Some("°"), 0.01;
+    GpsVL => "GPSVL", GPSVL, RO, U, None, 1.0;
+    GpsVH => "GPSVH", GPSVH, RO, S, None, 1.0;
+    Q0 => "Q0", Q0, RO, S, None, UNIT_LSB;
+    Q1 => "Q1", Q1, RO, S, None, UNIT_LSB;
+    Q2 => "Q2", Q2, RO, S, None, UNIT_LSB;
+    Q3 => "Q3", Q3, RO, S, None, UNIT_LSB;
+    SvNum => "SVNUM", SVNUM, RO, U, None, 1.0;
+    Pdop => "PDOP", PDOP, RO, U, None, 0.01;
+    Hdop => "HDOP", HDOP, RO, U, None, 0.01;
+    Vdop => "VDOP", VDOP, RO, U, None, 0.01;
+    DelayT => "DELAYT", DELAYT, RW, U, Some("ms"), 1.0;
+    XMin => "XMIN", XMIN, RW, S, Some("°"), ANGLE_LSB;
+    XMax => "XMAX", XMAX, RW, S, Some("°"), ANGLE_LSB;
+    BatVal => "BATVAL", BATVAL, RO, U, Some("V"), 0.01;
+    AlarmPin => "ALARMPIN", ALARMPIN, RW, U, None, 1.0;
+    YMin => "YMIN", YMIN, RW, S, Some("°"), ANGLE_LSB;
+    YMax => "YMAX", YMAX, RW, S, Some("°"), ANGLE_LSB;
+    GyroZScale => "GYROZSCALE", GYROZSCALE, RW, U, None, 1.0;
+    GyroCaliThr => "GYROCALITHR", GYROCALITHR, RW, U, None, 1.0;
+    AlarmLevel => "ALARMLEVEL", ALARMLEVEL, RW, U, None, 1.0;
+    GyroCalTime => "GYROCALTIME", GYROCALTIME, RW, U, Some("ms"), 1.0;
+    RefRoll => "REFROLL", REFROLL, RW, S, None, 1.0;
+    RefPitch => "REFPITCH", REFPITCH, RW, S, None, 1.0;
+    RefYaw => "REFYAW", REFYAW, RW, S, None, 1.0;
+    GpsType => "GPSTYPE", GPSTYPE, RW, U, None, 1.0;
+    TrigTime => "TRIGTIME", TRIGTIME, RW, U, None, 1.0;
+    Key => "KEY", KEY, WO, U, None, 1.0;
+    WError => "WERROR", WERROR, RO, U, None, 1.0;
+    TimeZone => "TIMEZONE", TIMEZONE, RW, U, None, 1.0;
+    CaliCnt => "CALICNT", CALICNT, RO, U, None, 1.0;
+    WzCnt => "WZCNT", WZCNT, RW, U, None, 1.0;
+    WzTime => "WZTIME", WZTIME, RW, U, Some("ms"), 1.0;
+    WzStatic => "WZSTATIC", WZSTATIC, RW, U, None, 1.0;
+    AccSensor => "ACCSENSOR", ACCSENSOR, RO, U, None, 1.0;
+    GyroSensor => "GYROSENSOR", GYROSENSOR, RO, U, None, 1.0;
+    MagSensor => "MAGSENSOR", MAGSENSOR, RO, U, None, 1.0;
+    PresSensor => "PRESSENSOR", PRESSENSOR, RO, U, None, 1.0;
+    ModDelay => "MODDELAY", MODDELAY, RW, U, Some("us"), 1.0;
+    AngleAxis => "ANGLEAXIS", ANGLEAXIS, RW, U, None, 1.0;
+    XrScale => "XRSCALE", XRSCALE, RW, U, None, 1.0;
+    YrScale => "YRSCALE", YRSCALE, RW, U, None, 1.0;
+    ZrScale => "ZRSCALE", ZRSCALE, RW, U, None, 1.0;
+    XRefRoll => "XREFROLL", XREFROLL, RW, S, None, 1.0;
+    YRefPitch => "YREFPITCH", YREFPITCH, RW, S, None, 1.0;
+    ZRefYaw => "ZREFYAW", ZREFYAW, RW, S, None, 1.0;
+    AngXOffset => "ANGXOFFSET", ANGXOFFSET, RW, S, None, 1.0;
+    AngYOffset => "ANGYOFFSET", ANGYOFFSET, RW, S, None, 1.0;
+    AngZOffset => "ANGZOFFSET", ANGZOFFSET, RW, S, None, 1.0;
+    NumberId1 => "NUMBERID1", NUMBERID1, RO, U, None, 1.0;
+    NumberId2 => "NUMBERID2", NUMBERID2, RO, U, None, 1.0;
+    NumberId3 => "NUMBERID3", NUMBERID3, RO, U, None, 1.0;
+    NumberId4 => "NUMBERID4", NUMBERID4, RO, U, None, 1.0;
+    NumberId5 => "NUMBERID5", NUMBERID5, RO, U, None, 1.0;
+    NumberId6 => "NUMBERID6", NUMBERID6, RO, U, None, 1.0;
+    Xa85PScale => "XA85PSCALE", XA85PSCALE, RW, U, None, 1.0;
+    Xa85NScale => "XA85NSCALE", XA85NSCALE, RW, U, None, 1.0;
+    Ya85PScale => "YA85PSCALE", YA85PSCALE, RW, U, None, 1.0;
+    Ya85NScale => "YA85NSCALE", YA85NSCALE, RW, U, None, 1.0;
+    Xa30PScale => "XA30PSCALE", XA30PSCALE, RW, U, None, 1.0;
+    Xa30NScale => "XA30NSCALE", XA30NSCALE, RW, U, None, 1.0;
+    Ya30PScale => "YA30PSCALE", YA30PSCALE, RW, U, None, 1.0;
+    Ya30NScale => "YA30NSCALE", YA30NSCALE, RW, U, None, 1.0;
+    ChipIdL => "CHIPIDL", CHIPIDL, RO, U, None, 1.0;
+    ChipIdH => "CHIPIDH", CHIPIDH, RO, U, None, 1.0;
+    RegInitFlag => "REGINITFLAG", REGINITFLAG, RO, U, None, 1.0;
+}
+
+impl Register {
+    /// Register name as spelled in REG.h (upper case)
+    pub fn name(&self) -> &'static str {
+        self.info().name
+    }
+
+    /// Register address
+    pub fn address(&self) -> u16 {
+        self.info().address
+    }
+
+    /// How the register can be accessed
+    pub fn access(&self) -> RegisterAccess {
+        self.info().access
+    }
+
+    /// Whether the raw value is a signed 16-bit integer
+    pub fn is_signed(&self) -> bool {
+        self.info().signed
+    }
+
+    /// Physical unit of the scaled value, if it has one
+    pub fn unit(&self) -> Option<&'static str> {
+        self.info().unit
+    }
+
+    /// Factor converting the raw value to the physical unit
+    /// Acceleration and angular rate assume the default ±16g and ±2000°/s ranges
+    pub fn scale(&self) -> f32 {
+        self.info().scale
+    }
+
+    /// Convert a raw register value to its physical value
+    pub fn to_physical(&self, raw: i16) -> f32 {
+        let value = if self.is_signed() { raw as f32 } else { raw as u16 as f32 };
+        value * self.scale()
+    }
+
+    /// Look up the register at an address, preferring the standard WT901 meaning
+    pub fn from_address(address: u16) -> Option<Register> {
+        Self::ALL.iter().copied().find(|register| register.address() == address)
+    }
+
+    /// All registers sharing an address
+    pub fn aliases(address: u16) -> Vec<Register> {
+        Self::ALL
+            .iter()
+            .copied()
+            .filter(|register| register.address() == address)
+            .collect()
+    }
+}
+
+/// Name of a register for messages
+pub(crate) fn register_name(register: u16) -> String {
+    Register::from_address(register)
+        .map(|register| register.name().to_string())
+        .unwrap_or_else(|| format!("0x{:02X}", register))
+}
+
+impl From<Register> for u16 {
+    fn from(register: Register) -> u16 {
+        register.address()
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Register {
+    type Err = WitError;
+
+    /// Parse a register name, ignoring case ("GYRORANGE", "GPSHeight")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|register| register.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| WitError::InvalidParameter(format!("Unknown register: {}", s)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_metadata() {
+        assert_eq!("gyrorange".parse::<Register>().unwrap(), Register::GyroRange);
+        assert_eq!(Register::GyroRange.to_string(), "GYRORANGE");
+        assert_eq!(u16::from(Register::GyroRange), 0x20);
+        assert!("NOTAREG".parse::<Register>().is_err());
+
+        assert_eq!(Register::from_address(0x3D), Some(Register::Roll));
+        assert_eq!(Register::aliases(0x43), vec![Register::Temp905x, Register::D2Status]);
+        assert_eq!(Register::Roll.to_physical(16384), 90.0);
+        assert_eq!(Register::GpsYaw.to_physical(-1), 655.35);
+        assert!(!Register::Key.access().is_readable());
+        assert!(!Register::Version.access().is_writable());
+
+        // Every address in the map is covered
+        for address in 0..REGSIZE as u16 {
+            assert!(Register::from_address(address).is_some(), "0x{:02X}", address);
+        }
+    }
+}
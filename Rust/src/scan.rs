@@ -0,0 +1,460 @@
+use crate::{
+    error::{WitError, WitResult},
+    modbus::{create_read_request, parse_response, MAX_SLAVE_ADDRESS},
+    model::SensorModel,
+    normal::NormalProtocol,
+    protocol::WitProtocol,
+    registers::*,
+    serial::{WitSerial, DEFAULT_READ_TIMEOUT},
+};
+use std::{
+    ops::RangeInclusive,
+    time::{Duration, Instant},
+};
+
+/// Modbus addresses probed by default: the factory default address and broadcast
+pub const DEFAULT_PROBE_ADDRESSES: &[u8] = &[0x50, 0xFF];
+
+/// Valid normal protocol packets required before a stream is accepted
+const MIN_NORMAL_PACKETS: usize = 2;
+
+/// Registers read when probing Modbus (ROLL..TEMP905X covers both angle layouts)
+const PROBE_COUNT: u16 = TEMP905X - ROLL + 1;
+
+/// Time a sensor and USB adapter may take before the first byte of an answer
+const BUS_TURNAROUND: Duration = Duration::from_millis(20);
+
+/// Result of a successful sensor scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanResult {
+    /// Baud rate the sensor was found at
+    pub baud: u32,
+    /// Protocol the sensor speaks
+    pub protocol: WitProtocol,
+    /// Modbus slave address that answered (None for streaming protocols)
+    pub address: Option<u8>,
+    /// Sensor family, if it could be told from the traffic
+    pub model: Option<SensorModel>,
+}
+
+/// Options controlling how each baud rate is probed
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// How long to listen for unsolicited 0x55 packets
+    pub listen_time: Duration,
+    /// How long to wait for a Modbus response from each address
+    pub response_timeout: Duration,
+    /// Modbus slave addresses to probe, in order
+    pub addresses: Vec<u8>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            listen_time: Duration::from_millis(300),
+            response_timeout: Duration::from_millis(100),
+            addresses: DEFAULT_PROBE_ADDRESSES.to_vec(),
+        }
+    }
+}
+
+/// A Modbus sensor found by a bus scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusDevice {
+    /// Baud rate the sensor answered at
+    pub baud: u32,
+    /// Modbus slave address of the sensor
+    pub address: u8,
+    /// Protocol the sensor speaks
+    pub protocol: WitProtocol,
+    /// Sensor family, told from the angle layout
+    pub model: SensorModel,
+    /// Firmware VERSION register
+    pub version: u16,
+}
+
+/// Scan the given baud rates until a sensor speaking any supported protocol is found
+pub fn scan(serial: &mut WitSerial, baud_rates: &[u32], options: &ScanOptions) -> WitResult<ScanResult> {
+    for &baud_rate in baud_rates {
+        if serial.set_baud_rate(baud_rate).is_err() {
+            continue;
+        }
+
+        if let Some(result) = detect(serial, options)? {
+            return Ok(result);
+        }
+    }
+
+    Err(WitError::SensorNotFound)
+}
+
+/// Detect a sensor at the current baud rate of the port
+///
+/// Unsolicited 0x55 packets are listened for first, since a streaming sensor
+/// would corrupt any Modbus exchange, then each Modbus address is probed.
+pub fn detect(serial: &mut WitSerial, options: &ScanOptions) -> WitResult<Option<ScanResult>> {
+    serial.clear_input_buffer()?;
+
+    if let Some(result) = listen_normal(serial, options.listen_time)? {
+        return Ok(Some(result));
+    }
+
+    for &address in &options.addresses {
+        if let Some(result) = probe_modbus(serial, address, options.response_timeout)? {
+            return Ok(Some(result));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Find every Modbus sensor answering in an address range at the given baud rates
+///
+/// Each address gets a one register probe with a timeout sized for the baud
+/// rate, so a full 1..=247 scan takes about 6 seconds at 115200 baud and 10 at 9600.
+/// Addresses must be within 1..=247, since every sensor answers broadcast at once.
+/// The read timeout of the port is shortened during the scan and restored after.
+pub fn scan_bus(
+    serial: &mut WitSerial,
+    baud_rates: &[u32],
+    addresses: RangeInclusive<u8>,
+) -> WitResult<Vec<BusDevice>> {
+    if *addresses.start() == 0 || *addresses.end() > MAX_SLAVE_ADDRESS {
+        return Err(WitError::InvalidParameter(format!(
+            "Invalid address range: {}..={} (expected 1 to {})",
+            addresses.start(),
+            addresses.end(),
+            MAX_SLAVE_ADDRESS
+        )));
+    }
+
+    serial.set_read_timeout(Duration::from_millis(1))?;
+    let result = scan_bus_addresses(serial, baud_rates, addresses);
+    serial.set_read_timeout(DEFAULT_READ_TIMEOUT)?;
+    result
+}
+
+fn scan_bus_addresses(
+    serial: &mut WitSerial,
+    baud_rates: &[u32],
+    addresses: RangeInclusive<u8>,
+) -> WitResult<Vec<BusDevice>> {
+    let mut devices = Vec::new();
+
+    for &baud_rate in baud_rates {
+        if serial.set_baud_rate(baud_rate).is_err() {
+            continue;
+        }
+        // Silent addresses dominate, so they only cost a one register read
+        let timeout = bus_response_timeout(serial, 1);
+        let probe_timeout = bus_response_timeout(serial, PROBE_COUNT);
+
+        for address in addresses.clone() {
+            serial.clear_input_buffer()?;
+            // A late answer from another slave must not be recorded under this address
+            let Some((answered, version)) = read_modbus(serial, address, VERSION, 1, timeout)? else {
+                continue;
+            };
+            if answered != address {
+                continue;
+            }
+            let result = probe_modbus(serial, address, probe_timeout)?.filter(|result| result.address == Some(address));
+
+            devices.push(BusDevice {
+                baud: baud_rate,
+                address,
+                protocol: result.map_or(WitProtocol::Modbus, |result| result.protocol),
+                model: result.and_then(|result| result.model).unwrap_or(SensorModel::Wt901C485),
+                version: version[0],
+            });
+        }
+    }
+
+    Ok(devices)
+}
+
+/// How long to wait for a read of `count` registers at the current baud rate
+fn bus_response_timeout(serial: &WitSerial, count: u16) -> Duration {
+    // addr + func + len + data + 2*CRC
+    serial.transmission_time(count as usize * 2 + 5) + BUS_TURNAROUND
+}
+
+/// Listen for a normal protocol stream
+fn listen_normal(serial: &mut WitSerial, listen_time: Duration) -> WitResult<Option<ScanResult>> {
+    let mut protocol = NormalProtocol::new();
+    let mut packets = 0;
+    let mut has_magnetometer = false;
+    let deadline = Instant::now() + listen_time;
+
+    while Instant::now() < deadline {
+        let Some(byte) = serial.read_byte()? else {
+            continue;
+        };
+
+        if let Some(blocks) = protocol.process_byte(byte)? {
+            packets += 1;
+            has_magnetometer |= blocks.iter().any(|(start, _)| *start == HX);
+        }
+    }
+
+    if packets < MIN_NORMAL_PACKETS {
+        return Ok(None);
+    }
+
+    Ok(Some(ScanResult {
+        baud: serial.baud_rate(),
+        protocol: WitProtocol::Normal,
+        address: None,
+        model: has_magnetometer.then_some(SensorModel::Wt901),
+    }))
+}
+
+/// Probe a Modbus address and classify the angle layout of the sensor that answers
+fn probe_modbus(serial: &mut WitSerial, address: u8, timeout: Duration) -> WitResult<Option<ScanResult>> {
+    let Some((answered, values)) = read_modbus(serial, address, ROLL, PROBE_COUNT, timeout)? else {
+        return Ok(None);
+    };
+
+    let values: Vec<i16> = values.into_iter().map(|value| value as i16).collect();
+    let (protocol, model) = if looks_like_905x(&values) {
+        (WitProtocol::Modbus905x, SensorModel::Hwt905)
+    } else {
+        (WitProtocol::Modbus, SensorModel::Wt901C485)
+    };
+
+    Ok(Some(ScanResult {
+        baud: serial.baud_rate(),
+        protocol,
+        // Report the address the sensor answered from, not the broadcast address
+        address: Some(answered),
+        model: Some(model),
+    }))
+}
+
+/// Read registers from a Modbus address
+/// Returns the address that answered and the register values
+fn read_modbus(
+    serial: &mut WitSerial,
+    address: u8,
+    start_register: u16,
+    count: u16,
+    timeout: Duration,
+) -> WitResult<Option<(u8, Vec<u16>)>> {
+    let request = create_read_request(address, start_register, count);
+    serial.write(&request)?;
+    serial.flush()?;
+
+    let deadline = Instant::now() + serial.transmission_time(request.len()) + timeout;
+    let mut frame = Vec::new();
+
+    while Instant::now() < deadline {
+        let Some(byte) = serial.read_byte()? else {
+            continue;
+        };
+        frame.push(byte);
+
+        // Drop leading bytes until the buffer starts like a read response
+        if frame.len() >= 2 && frame[1] != FUNC_READ {
+            frame.remove(0);
+            continue;
+        }
+
+        if frame.len() < 5 || frame.len() < frame[2] as usize + 5 {
+            continue;
+        }
+
+        match parse_response(&frame) {
+            Ok(values) => return Ok(Some((frame[0], values))),
+            Err(_) => {
+                frame.remove(0);
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Tell WT905x sensors apart from 16-bit angle sensors
+///
+/// WT905x sensors hold angles as 32-bit millidegrees from ROLL onwards, so the
+/// high words at 0x3E/0x40/0x42 stay within -3..=2. On other sensors 0x40 is
+/// TEMP in hundredths of a degree, far outside that range in normal operation.
+fn looks_like_905x(values: &[i16]) -> bool {
+    values.len() >= 6 && [values[1], values[3], values[5]].iter().all(|value| (-3..=2).contains(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ROLL..TEMP905X as a WT905x sends them: 32-bit millidegree angles, then temperature
+    fn angles_905x(roll: i32, pitch: i32, yaw: i32) -> Vec<i16> {
+        let mut values = Vec::new();
+        for angle in [roll, pitch, yaw] {
+            values.extend([angle as i16, (angle >> 16) as i16]);
+        }
+        values.push(2512);
+        values
+    }
+
+    #[test]
+    fn test_905x_heuristic() {
+        // ROLL, PITCH, YAW, TEMP, then registers that differ between firmwares
+        let standard = |temperature: i16| vec![1820, -910, 16384, temperature, 0, 0, 0];
+
+        let cases = [
+            (angles_905x(0, 0, 0), true),
+            (angles_905x(180_000, -180_000, 90_000), true),
+            (angles_905x(-1, 179_999, -45_500), true),
+            (standard(2512), false),
+            (standard(-1050), false),
+            // Near 0 °C the pitch still gives a tilted 16-bit sensor away
+            (vec![0, 546, 16384, 1, 0, 0, 0], false),
+            (angles_905x(0, 0, 0)[..5].to_vec(), false),
+        ];
+        for (values, expected) in cases {
+            assert_eq!(looks_like_905x(&values), expected, "{:?}", values);
+        }
+    }
+
+    /// Answer Modbus reads on the other end of a pseudo-terminal as the sensor at `address`
+    /// Its registers from `first` on hold `values`, the others read as 0.
+    /// The port stays with the caller, closing it would fail reads on the other end.
+    #[cfg(unix)]
+    fn answer_reads(port: &mut serial2::SerialPort, address: u8, first: u16, values: Vec<i16>, requests: usize) {
+        answer_reads_to(port, &[address, 0xFF], address, first, values, requests);
+    }
+
+    /// Like `answer_reads`, but answering requests sent to any of `heard`
+    #[cfg(unix)]
+    fn answer_reads_to(
+        port: &mut serial2::SerialPort,
+        heard: &[u8],
+        address: u8,
+        first: u16,
+        values: Vec<i16>,
+        requests: usize,
+    ) {
+        use crc::{Crc, CRC_16_MODBUS};
+
+        let crc = Crc::<u16>::new(&CRC_16_MODBUS);
+        port.set_read_timeout(Duration::from_secs(2)).unwrap();
+
+        for _ in 0..requests {
+            let mut request = [0u8; 8];
+            port.read_exact(&mut request).unwrap();
+            if !heard.contains(&request[0]) {
+                continue;
+            }
+
+            let start = u16::from_be_bytes([request[2], request[3]]);
+            let count = u16::from_be_bytes([request[4], request[5]]);
+            let mut response = vec![address, FUNC_READ, count as u8 * 2];
+            for register in start..start + count {
+                let value = register.checked_sub(first).and_then(|i| values.get(i as usize)).unwrap_or(&0);
+                response.extend_from_slice(&value.to_be_bytes());
+            }
+            response.extend_from_slice(&crc.checksum(&response).to_le_bytes());
+            port.write_all(&response).unwrap();
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_detect_protocol_and_address() {
+        use std::thread;
+
+        let options = ScanOptions {
+            listen_time: Duration::from_millis(20),
+            response_timeout: Duration::from_millis(100),
+            addresses: vec![0x51, 0xFF],
+        };
+
+        // A WT905x answering the broadcast probe is reported at its own address
+        let (mut serial, mut port) = WitSerial::pair().unwrap();
+        let sensor = thread::spawn(move || {
+            answer_reads(&mut port, 0x50, ROLL, angles_905x(1500, -2500, 170_000), 2);
+            port
+        });
+        let result = detect(&mut serial, &options).unwrap().unwrap();
+        sensor.join().unwrap();
+        assert_eq!(result.protocol, WitProtocol::Modbus905x);
+        assert_eq!(result.address, Some(0x50));
+        assert_eq!(result.model, Some(SensorModel::Hwt905));
+        assert_eq!(result.baud, 9600);
+
+        // A 16-bit sensor at the first probed address
+        let (mut serial, mut port) = WitSerial::pair().unwrap();
+        let sensor = thread::spawn(move || {
+            answer_reads(&mut port, 0x51, ROLL, vec![1820, -910, 16384, 2512], 1);
+            port
+        });
+        let result = detect(&mut serial, &options).unwrap().unwrap();
+        sensor.join().unwrap();
+        assert_eq!(result.protocol, WitProtocol::Modbus);
+        assert_eq!(result.address, Some(0x51));
+        assert_eq!(result.model, Some(SensorModel::Wt901C485));
+
+        // Nobody answers
+        let (mut serial, _port) = WitSerial::pair().unwrap();
+        assert!(detect(&mut serial, &options).unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_response_timeout_follows_baud_rate() {
+        let (mut serial, _port) = WitSerial::pair().unwrap();
+
+        // 7 bytes for one register: 7.3 ms at 9600 baud, 0.6 ms at 115200
+        assert_eq!(bus_response_timeout(&serial, 1), Duration::from_micros(7291) + BUS_TURNAROUND);
+        assert_eq!(
+            bus_response_timeout(&serial, PROBE_COUNT),
+            serial.transmission_time(PROBE_COUNT as usize * 2 + 5) + BUS_TURNAROUND
+        );
+        serial.set_baud_rate(115200).unwrap();
+        assert_eq!(bus_response_timeout(&serial, 1), Duration::from_micros(607) + BUS_TURNAROUND);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_bus_reports_each_sensor() {
+        use std::thread;
+
+        // VERSION, then ROLL..TEMP905X of a WT905x
+        let mut values = vec![0; (ROLL - VERSION) as usize];
+        values[0] = 0x1234;
+        values.extend(angles_905x(0, 0, 90_000));
+
+        // One version read per address, then the probe of the one that answered
+        let (mut serial, mut port) = WitSerial::pair().unwrap();
+        let sensor = thread::spawn(move || {
+            answer_reads(&mut port, 0x50, VERSION, values, 5);
+            port
+        });
+        let devices = scan_bus(&mut serial, &[9600], 0x4F..=0x52).unwrap();
+
+        assert_eq!(
+            devices,
+            [BusDevice {
+                baud: 9600,
+                address: 0x50,
+                protocol: WitProtocol::Modbus905x,
+                model: SensorModel::Hwt905,
+                version: 0x1234,
+            }]
+        );
+
+        // An answer from 0x50 to the request for 0x51 is not a sensor at 0x51
+        let sensor = thread::spawn(move || {
+            let mut port = sensor.join().unwrap();
+            answer_reads_to(&mut port, &[0x51], 0x50, VERSION, vec![0x1234], 1);
+            port
+        });
+        assert!(scan_bus(&mut serial, &[9600], 0x51..=0x51).unwrap().is_empty());
+        sensor.join().unwrap();
+
+        // Address 0 and broadcast are never probed
+        assert!(matches!(scan_bus(&mut serial, &[9600], 0..=0x10), Err(WitError::InvalidParameter(_))));
+        assert!(matches!(scan_bus(&mut serial, &[9600], 0xF0..=0xFF), Err(WitError::InvalidParameter(_))));
+    }
+}
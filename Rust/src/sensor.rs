@@ -1,23 +1,60 @@
 use crate::{
     error::{WitError, WitResult},
-    modbus::ModbusProtocol,
+    jy61::Jy61Command,
+    model::SensorModel,
+    modbus::{BROADCAST_ADDRESS, MAX_SLAVE_ADDRESS},
+    profile::{Capabilities, DeviceProfile},
+    protocol::WitProtocol,
     registers::*,
+    scan::{self, BusDevice, ScanOptions, ScanResult},
     serial::WitSerial,
-    SUPPORTED_BAUD_RATES, DEFAULT_READ_COUNT,
+    settings::{AccRange, AlgorithmAxes, BaudRate, Bandwidth, GyroRange, Orientation, OutputContent, OutputRate, RegisterValue},
+    transaction::{ConfigTransaction, MismatchPolicy, RegisterMismatch},
+    transport::{SerialTransport, Transport},
+    SUPPORTED_BAUD_RATES,
 };
 use bitflags::bitflags;
-use std::{collections::HashMap, thread, time::Duration};
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    thread,
+    time::{Duration, Instant},
+};
 
 /// Scaling factors for sensor data conversion
-/// Accelerometer: ±16g range over 16-bit signed integer
+/// Accelerometer: factory ±16g range over 16-bit signed integer
+/// The sensor scales with its configured range, see `MeasurementRanges`
 pub const ACC_SCALE: f32 = 16.0 / 32768.0;
-/// Gyroscope: ±2000°/s range over 16-bit signed integer  
+/// Gyroscope: factory ±2000°/s range over 16-bit signed integer
+/// The sensor scales with its configured range, see `MeasurementRanges`
 pub const GYRO_SCALE: f32 = 2000.0 / 32768.0;
 /// Angle: ±180° range over 16-bit signed integer
 pub const ANGLE_SCALE: f32 = 180.0 / 32768.0;
+/// High precision angle (WT905x): 32-bit signed integer in thousandths of a degree
+pub const ANGLE_905X_SCALE: f32 = 1.0 / 1000.0;
 /// Magnetometer: raw values (no scaling)
 pub const MAG_SCALE: f32 = 1.0;
 
+/// Full scale ranges used to scale accelerometer and gyroscope readings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeasurementRanges {
+    pub acc: AccRange,
+    pub gyro: GyroRange,
+}
+
+impl Default for MeasurementRanges {
+    /// Factory ranges: ±16 g and ±2000 °/s
+    fn default() -> Self {
+        Self {
+            acc: AccRange::G16,
+            gyro: GyroRange::Dps2000,
+        }
+    }
+}
+
+/// How long to wait for the answer to a single register read
+pub const REGISTER_QUERY_TIMEOUT: Duration = Duration::from_millis(500);
+
 bitflags! {
     /// Flags indicating which sensor data has been updated
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -26,18 +63,40 @@ bitflags! {
         const GYRO = 0x02;     // Gyroscope data updated  
         const ANGLE = 0x04;    // Angle data updated
         const MAG = 0x08;      // Magnetometer data updated
+        const PRESSURE = 0x10; // Pressure and height updated
+        const GPS = 0x20;      // GPS data updated
+        const QUATERNION = 0x40; // Quaternion updated
         const READ = 0x80;     // Generic read update
     }
 }
 
+/// Position and motion reported by a GPS module attached to the sensor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsData {
+    pub longitude: f64, // degrees
+    pub latitude: f64, // degrees
+    pub height: f32, // m
+    pub yaw: f32, // degrees
+    pub speed: f32, // km/h
+}
+
 /// Sensor data structure containing scaled measurements
 #[derive(Debug, Clone)]
 pub struct SensorData {
     pub accelerometer: [f32; 3], // [x, y, z]
     pub gyroscope: [f32; 3], // [x, y, z]
     pub angles: [f32; 3], // [roll, pitch, yaw]
-    pub magnetometer: [i16; 3], // [x, y, z]
+    /// None on sensors without a magnetometer (JY61)
+    pub magnetometer: Option<[i16; 3]>, // [x, y, z]
     pub temperature: f32,
+    /// Barometric pressure in Pa, on models with a barometer once received
+    pub pressure: Option<i32>,
+    /// Barometric height in m, on models with a barometer once received
+    pub height: Option<f32>,
+    /// GPS data, on models with GPS input once received
+    pub gps: Option<GpsData>,
+    /// Quaternion [q0, q1, q2, q3], once received
+    pub quaternion: Option<[f32; 4]>,
     /// Flags indicating which data was updated
     pub update_flags: DataUpdateFlags,
 }
@@ -48,8 +107,12 @@ impl Default for SensorData {
             accelerometer: [0.0; 3],
             gyroscope: [0.0; 3],
             angles: [0.0; 3],
-            magnetometer: [0; 3],
+            magnetometer: None,
             temperature: 0.0,
+            pressure: None,
+            height: None,
+            gps: None,
+            quaternion: None,
             update_flags: DataUpdateFlags::empty(),
         }
     }
@@ -80,186 +143,484 @@ impl SensorData {
     pub fn has_magnetometer_update(&self) -> bool {
         self.update_flags.contains(DataUpdateFlags::MAG)
     }
+
+    /// Check if pressure and height were updated
+    pub fn has_pressure_update(&self) -> bool {
+        self.update_flags.contains(DataUpdateFlags::PRESSURE)
+    }
+
+    /// Check if GPS data was updated
+    pub fn has_gps_update(&self) -> bool {
+        self.update_flags.contains(DataUpdateFlags::GPS)
+    }
+
+    /// Check if the quaternion was updated
+    pub fn has_quaternion_update(&self) -> bool {
+        self.update_flags.contains(DataUpdateFlags::QUATERNION)
+    }
 }
 
 /// Main WitMotion sensor interface
-pub struct WitSensor {
-    serial: WitSerial,
-    modbus: ModbusProtocol,
+///
+/// Generic over the transport used to reach the sensor; by default it talks
+/// over a serial port. Register values are decoded according to the device
+/// profile, which is guessed from the protocol until set explicitly.
+pub struct WitSensor<T: Transport = SerialTransport> {
+    transport: T,
+    profile: &'static DeviceProfile,
     registers: HashMap<u16, i16>,
-    current_baud: u32,
+    ranges: MeasurementRanges,
 }
 
-impl WitSensor {
+impl WitSensor<SerialTransport> {
     /// Create a new WitMotion sensor interface
     pub fn new(
         device_path: &str,
         slave_address: u8,
+    ) -> WitResult<Self> {
+        Self::with_protocol(device_path, slave_address, WitProtocol::Modbus)
+    }
+
+    /// Create a new WitMotion sensor interface speaking the given protocol
+    /// The slave address is only used by the Modbus protocols
+    pub fn with_protocol(
+        device_path: &str,
+        slave_address: u8,
+        protocol: WitProtocol,
     ) -> WitResult<Self> {
         // Start with 9600 as default baud rate for initial connection
         let default_baud = 9600;
-        let serial = WitSerial::open(device_path, default_baud)?;        
-        let modbus = ModbusProtocol::new(slave_address);
-        let registers = HashMap::new();
+        let serial = WitSerial::open(device_path, default_baud)?;
 
-        Ok(Self {
-            serial,
-            modbus,
-            registers,
-            current_baud: default_baud,
-        })
+        Ok(Self::with_transport(SerialTransport::new(serial, slave_address, protocol)))
     }
 
-    /// Initialize the sensor
-    pub fn init(&mut self) -> WitResult<()> {
-        // No initialization needed for PC-based operation
+    /// Auto-scan for the sensor by trying different baud rates and protocols
+    ///
+    /// The configured slave address is probed before the factory default and
+    /// broadcast addresses. On success the sensor switches to the baud rate,
+    /// protocol and address that were found.
+    pub fn auto_scan(&mut self) -> WitResult<ScanResult> {
+        let mut options = ScanOptions::default();
+        let address = self.transport.slave_address();
+        options.addresses.retain(|&probe| probe != address);
+        options.addresses.insert(0, address);
+
+        self.auto_scan_with(SUPPORTED_BAUD_RATES, &options)
+    }
+
+    /// Auto-scan for the sensor with custom baud rates and scan options
+    pub fn auto_scan_with(&mut self, baud_rates: &[u32], options: &ScanOptions) -> WitResult<ScanResult> {
+        let previous_baud = self.current_baud_rate();
+        let mut result = match scan::scan(self.transport.serial_mut(), baud_rates, options) {
+            Ok(result) => result,
+            Err(e) => {
+                // Go back to the rate we were using before the scan
+                self.transport.serial_mut().set_baud_rate(previous_baud)?;
+                return Err(e);
+            }
+        };
+
+        // JY61 sensors stream the same packets as normal protocol ones
+        if result.protocol == WitProtocol::Normal && self.protocol() == WitProtocol::Jy61 {
+            result.protocol = WitProtocol::Jy61;
+            result.model = Some(SensorModel::Jy61);
+        }
+
+        let address = result.address.unwrap_or(self.transport.slave_address());
+        self.transport.reconfigure(result.protocol, address);
+        self.profile = match result.model {
+            Some(model) => DeviceProfile::for_model(model),
+            None => DeviceProfile::for_protocol(result.protocol),
+        };
+
+        Ok(result)
+    }
+
+    /// Find every Modbus sensor on the bus in an address range
+    ///
+    /// The port goes back to its baud rate afterwards and the sensor keeps
+    /// talking to its configured address. Addresses must be within 1..=247.
+    pub fn scan_bus(&mut self, baud_rates: &[u32], addresses: RangeInclusive<u8>) -> WitResult<Vec<BusDevice>> {
+        let previous_baud = self.current_baud_rate();
+        let result = scan::scan_bus(self.transport.serial_mut(), baud_rates, addresses);
+        self.follow_baud_rate(previous_baud)?;
+        result
+    }
+
+    /// Get the current baud rate
+    pub fn current_baud_rate(&self) -> u32 {
+        self.transport.serial().baud_rate()
+    }
+}
+
+impl<T: Transport> WitSensor<T> {
+    /// Create a sensor interface on top of an existing transport
+    pub fn with_transport(transport: T) -> Self {
+        Self {
+            profile: DeviceProfile::for_protocol(transport.protocol()),
+            transport,
+            registers: HashMap::new(),
+            ranges: MeasurementRanges::default(),
+        }
+    }
+
+    /// Send a command to a JY61 sensor
+    /// Baud rate commands also switch the port to the new rate
+    pub fn send_jy61_command(&mut self, command: Jy61Command) -> WitResult<()> {
+        if self.protocol() != WitProtocol::Jy61 {
+            return Err(WitError::InvalidParameter(
+                "JY61 commands can only be sent to JY61 sensors".to_string(),
+            ));
+        }
+
+        self.transport.send_command(&command.encode())?;
+        if let Some(baud) = command.baud_rate() {
+            self.follow_baud_rate(baud)?;
+        }
         Ok(())
     }
 
-    /// Auto-scan for the sensor by trying different baud rates
-    pub fn auto_scan(&mut self) -> WitResult<u32> {
-        println!("Scanning for sensor...");
-        
-        for &baud_rate in SUPPORTED_BAUD_RATES {
-            println!("Trying baud rate: {}", baud_rate);
-            
-            if let Ok(()) = self.serial.set_baud_rate(baud_rate) {
-                self.current_baud = baud_rate;
-                
-                // Clear any existing data
-                self.serial.clear_input_buffer()?;
-                
-                // Try to read some registers
-                for _retry in 0..2 {
-                    if let Ok(()) = self.read_registers(AX, 3) {
-                        thread::sleep(Duration::from_millis(200));
-                        
-                        // Check if we received any data
-                        if self.process_incoming_data()?.is_some() {
-                            println!("Found sensor at {} baud", baud_rate);
-                            return Ok(baud_rate);
-                        }
-                    }
+    /// Change the baud rate of the sensor and follow it to the new rate
+    ///
+    /// The sensor switches as soon as BAUD is written. The port follows, the
+    /// link is confirmed by reading BAUD back and only then is the rate saved.
+    /// If the sensor does not answer at the new rate the port goes back to the
+    /// old one and `WitError::VerificationFailed` is returned, or
+    /// `WitError::SensorNotFound` if it does not answer there either.
+    pub fn change_baud_rate(&mut self, baud: BaudRate) -> WitResult<()> {
+        if self.protocol() == WitProtocol::Jy61 {
+            let command = match baud {
+                BaudRate::B9600 => Jy61Command::Baud9600,
+                BaudRate::B115200 => Jy61Command::Baud115200,
+                _ => {
+                    return Err(WitError::InvalidParameter(format!(
+                        "JY61 sensors only support 9600 and 115200 baud, not {}",
+                        baud.bits_per_second()
+                    )))
                 }
-            }
+            };
+            return self.send_jy61_command(command);
         }
-        
-        Err(WitError::SensorNotFound)
+        // The firmware refuses anything faster (WitSetUartBaud)
+        if matches!(baud, BaudRate::B460800 | BaudRate::B921600) {
+            return Err(WitError::InvalidParameter(format!(
+                "Sensors cannot be switched to {} baud, 230400 is the highest rate",
+                baud.bits_per_second()
+            )));
+        }
+        if !self.profile.supports(Register::Baud) {
+            return Err(WitError::InvalidParameter(format!(
+                "{} does not support changing the baud rate",
+                self.profile.model
+            )));
+        }
+
+        let old_baud = self.transport.baud_rate().ok_or_else(|| {
+            WitError::InvalidParameter("The baud rate can only be changed over a serial link".to_string())
+        })?;
+        if old_baud == baud.bits_per_second() {
+            return Ok(());
+        }
+
+        self.unlock()?;
+        self.write_register(BAUD, baud.into())?;
+        // Let the sensor answer and switch before the port does
+        thread::sleep(Duration::from_millis(50));
+        self.follow_baud_rate(baud.bits_per_second())?;
+
+        let actual = self.query_register(BAUD).ok();
+        if actual == Some(baud.into()) {
+            return self.save();
+        }
+
+        self.follow_baud_rate(old_baud)?;
+        if self.query_register(BAUD).is_err() {
+            return Err(WitError::SensorNotFound);
+        }
+        Err(WitError::VerificationFailed(vec![RegisterMismatch {
+            register: BAUD,
+            expected: baud.into(),
+            actual,
+        }]))
+    }
+
+    /// Give the sensor a new Modbus slave address (IICADDR) and follow it
+    ///
+    /// The new address is probed first and `WitError::AddressInUse` returned if
+    /// another sensor answers there. After the write the sensor is read back at
+    /// the new address and only then is the address saved. If it does not answer
+    /// the old address is restored and `WitError::VerificationFailed` returned.
+    pub fn change_address(&mut self, address: u8) -> WitResult<()> {
+        if !self.protocol().is_modbus() {
+            return Err(WitError::InvalidParameter(
+                "The slave address can only be changed on Modbus sensors".to_string(),
+            ));
+        }
+        if !(1..=MAX_SLAVE_ADDRESS).contains(&address) {
+            return Err(WitError::InvalidParameter(format!(
+                "Invalid slave address: {} (expected 1 to {})",
+                address, MAX_SLAVE_ADDRESS
+            )));
+        }
+
+        let old_address = self.transport.modbus_address().ok_or_else(|| {
+            WitError::InvalidParameter("The transport does not address Modbus slaves".to_string())
+        })?;
+        if old_address == BROADCAST_ADDRESS {
+            return Err(WitError::InvalidParameter(
+                "Cannot change the address through the broadcast address, every sensor would take it"
+                    .to_string(),
+            ));
+        }
+        if old_address == address {
+            return Ok(());
+        }
+
+        // Only silence means the address is free, garbled answers may be collisions
+        let probe = self.follow_address(address);
+        self.follow_address(old_address)?;
+        match probe {
+            Ok(_) => return Err(WitError::AddressInUse(address)),
+            Err(WitError::Timeout) => {}
+            Err(e) => return Err(e),
+        }
+
+        self.unlock()?;
+        self.write_register(IICADDR, address as u16)?;
+        thread::sleep(Duration::from_millis(20));
+
+        let actual = self.follow_address(address).ok();
+        if actual == Some(address as u16) {
+            return self.save();
+        }
+
+        if self.follow_address(old_address).is_err() {
+            return Err(WitError::SensorNotFound);
+        }
+        Err(WitError::VerificationFailed(vec![RegisterMismatch {
+            register: IICADDR,
+            expected: address as u16,
+            actual,
+        }]))
+    }
+
+    /// Talk to another slave address and read back its IICADDR
+    fn follow_address(&mut self, address: u8) -> WitResult<u16> {
+        self.transport.set_modbus_address(address)?;
+        self.transport.clear_input()?;
+        self.query_register(IICADDR)
+    }
+
+    /// Switch the port to a new baud rate, dropping data received at the old one
+    fn follow_baud_rate(&mut self, baud_rate: u32) -> WitResult<()> {
+        self.transport.set_baud_rate(baud_rate)?;
+        self.transport.clear_input()
+    }
+
+    /// Select the device profile used to decode register values
+    pub fn set_profile(&mut self, profile: &'static DeviceProfile) {
+        self.profile = profile;
+    }
+
+    /// Get the device profile used to decode register values
+    pub fn profile(&self) -> &'static DeviceProfile {
+        self.profile
+    }
+
+    /// Initialize the sensor
+    /// Reads the measurement ranges when the model allows register reads
+    pub fn init(&mut self) -> WitResult<()> {
+        if self.profile.has(Capabilities::REGISTER_ACCESS) {
+            self.refresh_ranges()?;
+        }
+        Ok(())
+    }
+
+    /// Get the ranges readings are scaled with
+    pub fn ranges(&self) -> MeasurementRanges {
+        self.ranges
+    }
+
+    /// Declare the ranges the sensor is configured for, instead of reading them
+    pub fn set_ranges(&mut self, ranges: MeasurementRanges) {
+        self.ranges = ranges;
+    }
+
+    /// Read ACCRANGE and GYRORANGE from the sensor and scale with them
+    pub fn refresh_ranges(&mut self) -> WitResult<MeasurementRanges> {
+        // Received values update the cached ranges
+        self.read_register_values(&[GYRORANGE, ACCRANGE])?;
+        Ok(self.ranges)
     }
 
     /// Read registers from the sensor
     pub fn read_registers(&mut self, start_register: u16, count: u16) -> WitResult<()> {
-        let request = self.modbus.generate_read_request(start_register, count);
-        self.send_data(&request)?;
-        Ok(())
+        self.transport.read_registers(start_register, count)
     }
-    
+
 
     /// Write a register value to the sensor
+    /// Writes to ACCRANGE or GYRORANGE change the scaling of later readings
     pub fn write_register(&mut self, register: u16, value: u16) -> WitResult<()> {
-        let request = self.modbus.generate_write_request(register, value);
-        self.send_data(&request)?;
+        self.transport.write_register(register, value)?;
+        self.update_ranges(register, value);
         Ok(())
     }
 
+    /// Read a single register and wait for its value
+    pub fn query_register(&mut self, register: u16) -> WitResult<u16> {
+        // Forget the cached value so only a fresh answer counts
+        self.registers.remove(&register);
+        self.read_registers(register, 1)?;
+
+        let deadline = Instant::now() + REGISTER_QUERY_TIMEOUT;
+        loop {
+            self.process_next_block()?;
+            if let Some(value) = self.get_register(register) {
+                return Ok(value as u16);
+            }
+            if Instant::now() >= deadline {
+                return Err(WitError::Timeout);
+            }
+        }
+    }
 
-    /// Send data via RS485 with proper direction control
-    fn send_data(&mut self, data: &[u8]) -> WitResult<()> {
-        // Calculate transmission delay based on baud rate
-        let delay_us = (1_000_000 * data.len() as u64 * 10) / self.current_baud as u64 + 300;
-        
-        // Send data (no GPIO control needed for PC)
-        self.serial.write(data)?;
-        self.serial.flush()?;
-        
-        // Wait for transmission to complete
-        thread::sleep(Duration::from_micros(delay_us));
-        
+    /// Unlock the configuration registers for the following writes
+    pub fn unlock(&mut self) -> WitResult<()> {
+        self.write_register(KEY, KEY_UNLOCK)?;
+        thread::sleep(Duration::from_millis(20));
         Ok(())
     }
 
     /// Process incoming data and return sensor data if available
     pub fn process_incoming_data(&mut self) -> WitResult<Option<SensorData>> {
-        let mut sensor_data = None;
-        
-        // Read all available bytes
-        loop {
-            match self.serial.read_byte()? {
-                Some(byte) => {
-                    if let Some((start_reg, values)) = self.modbus.process_byte(byte)? {
-                        // Update internal register storage
-                        for (i, &value) in values.iter().enumerate() {
-                            self.registers.insert(start_reg + i as u16, value);
-                        }
-                        
-                        // Convert to sensor data
-                        sensor_data = Some(self.extract_sensor_data(start_reg, &values));
-                    }
-                }
-                None => break, // No more data available
-            }
-            
-            // Reset buffer if it gets too large
-            if self.modbus.should_reset_buffer() {
-                self.modbus.clear_buffer();
+        let mut update_flags = None;
+
+        // Read all available register updates
+        while let Some((start_reg, values)) = self.transport.receive()? {
+            let flags = self.update_registers(start_reg, &values);
+            *update_flags.get_or_insert(DataUpdateFlags::empty()) |= flags;
+        }
+
+        Ok(update_flags.map(|flags| self.extract_sensor_data(flags)))
+    }
+
+    /// Process the next received register block, if any
+    /// Returns as soon as a block is in, where `process_incoming_data` waits for the line to go quiet
+    pub(crate) fn process_next_block(&mut self) -> WitResult<Option<SensorData>> {
+        match self.transport.receive()? {
+            Some((start_reg, values)) => {
+                let flags = self.update_registers(start_reg, &values);
+                Ok(Some(self.extract_sensor_data(flags)))
             }
+            None => Ok(None),
         }
-        
-        Ok(sensor_data)
     }
 
-    /// Extract and scale sensor data from raw register values
-    fn extract_sensor_data(&self, start_register: u16, values: &[i16]) -> SensorData {
-        let mut data = SensorData::new();
+    /// Store received register values and work out which sensor data they update
+    fn update_registers(&mut self, start_register: u16, values: &[i16]) -> DataUpdateFlags {
         let mut update_flags = DataUpdateFlags::empty();
-        
-        // Process each register value
+
+        // WT905x sensors use two registers per angle, which moves the temperature too
+        let angle_end = self.profile.angle_end();
+        let data_end = self.profile.data_end();
+
         for (i, &value) in values.iter().enumerate() {
             let reg = start_register + i as u16;
-            
-            match reg {
-                // Accelerometer registers (±16g range)
-                AX..=AZ => {
-                    let axis = (reg - AX) as usize;
-                    data.accelerometer[axis] = value as f32 / 32768.0 * 16.0;
-                    if reg == AZ {
-                        update_flags |= DataUpdateFlags::ACC;
-                    }
-                }
-                // Gyroscope registers (±2000°/s range)
-                GX..=GZ => {
-                    let axis = (reg - GX) as usize;
-                    data.gyroscope[axis] = value as f32 / 32768.0 * 2000.0;
-                    if reg == GZ {
-                        update_flags |= DataUpdateFlags::GYRO;
-                    }
+            self.registers.insert(reg, value);
+            self.update_ranges(reg, value as u16);
+
+            // Flag a group once its last register arrives, if the model has it
+            let supported = |register: Register| self.profile.supports(register);
+            update_flags |= match reg {
+                AZ => DataUpdateFlags::ACC,
+                GZ => DataUpdateFlags::GYRO,
+                HZ if supported(Register::Hz) => DataUpdateFlags::MAG,
+                _ if reg == angle_end => DataUpdateFlags::ANGLE,
+                HEIGHTH if supported(Register::HeightH) => DataUpdateFlags::PRESSURE,
+                LATH if supported(Register::LatH) => DataUpdateFlags::GPS,
+                GPSVH if supported(Register::GpsVH) => DataUpdateFlags::GPS,
+                Q3 if supported(Register::Q3) => DataUpdateFlags::QUATERNION,
+                _ if (AX..=data_end).contains(&reg) => DataUpdateFlags::empty(),
+                _ => DataUpdateFlags::READ,
+            };
+        }
+
+        update_flags
+    }
+
+    /// Keep the cached ranges in step with a range register value
+    fn update_ranges(&mut self, register: u16, value: u16) {
+        match register {
+            ACCRANGE => {
+                if let Ok(range) = AccRange::try_from(value) {
+                    self.ranges.acc = range;
                 }
-                // Magnetometer registers
-                HX..=HZ => {
-                    let axis = (reg - HX) as usize;
-                    data.magnetometer[axis] = value;
-                    if reg == HZ {
-                        update_flags |= DataUpdateFlags::MAG;
-                    }
+            }
+            GYRORANGE => {
+                if let Ok(range) = GyroRange::try_from(value) {
+                    self.ranges.gyro = range;
                 }
+            }
+            _ => {}
+        }
+    }
+
+    /// Extract and scale sensor data from the stored register values
+    fn extract_sensor_data(&self, update_flags: DataUpdateFlags) -> SensorData {
+        let mut data = SensorData::new();
+        let reg = |address: u16| self.get_register(address).unwrap_or(0);
+
+        // Scaled with the configured ranges
+        let acc_scale = self.ranges.acc.scale();
+        let gyro_scale = self.ranges.gyro.scale();
+        for axis in 0..3 {
+            data.accelerometer[axis] = reg(AX + axis as u16) as f32 * acc_scale;
+            data.gyroscope[axis] = reg(GX + axis as u16) as f32 * gyro_scale;
+        }
+
+        let profile = self.profile;
+        // Optional groups are only reported once the sensor has sent them
+        let received = |registers: &[u16]| registers.iter().all(|r| self.registers.contains_key(r));
+        let reg32 = |low: u16| register_pair(reg(low), reg(low + 1));
+
+        if profile.has(Capabilities::MAGNETOMETER) {
+            data.magnetometer = Some([reg(HX), reg(HY), reg(HZ)]);
+        }
+
+        if profile.has(Capabilities::HIGH_PRECISION_ANGLES) {
+            // High precision angle register pairs (low word first)
+            for (axis, low) in [LROLL, LPITCH, LYAW].into_iter().enumerate() {
+                data.angles[axis] = angle_905x(reg(low), reg(low + 1));
+            }
+        } else {
+            for axis in 0..3 {
                 // Angle registers (±180° range)
-                ROLL..=YAW => {
-                    let axis = (reg - ROLL) as usize;
-                    data.angles[axis] = value as f32 / 32768.0 * 180.0;
-                    if reg == YAW {
-                        update_flags |= DataUpdateFlags::ANGLE;
-                    }
-                }
-                // Temperature register
-                TEMP => {
-                    data.temperature = value as f32 / 100.0; // Assuming temperature scaling
-                }
-                _ => {
-                    update_flags |= DataUpdateFlags::READ;
-                }
+                data.angles[axis] = reg(ROLL + axis as u16) as f32 * ANGLE_SCALE;
             }
         }
-        
+        data.temperature = profile.temperature(reg(profile.temperature_register()));
+
+        if profile.has(Capabilities::PRESSURE) && received(&[PRESSUREL, PRESSUREH, HEIGHTL, HEIGHTH]) {
+            data.pressure = Some(reg32(PRESSUREL));
+            data.height = Some(reg32(HEIGHTL) as f32 / 100.0);
+        }
+
+        if profile.has(Capabilities::GPS) && received(&[LONL, LONH, LATL, LATH, GPSHEIGHT, GPSYAW, GPSVL, GPSVH]) {
+            data.gps = Some(GpsData {
+                longitude: gps_degrees(reg32(LONL)),
+                latitude: gps_degrees(reg32(LATL)),
+                height: reg(GPSHEIGHT) as f32 / 10.0,
+                yaw: reg(GPSYAW) as u16 as f32 / 100.0,
+                speed: reg32(GPSVL) as f32 / 1000.0,
+            });
+        }
+
+        if profile.has(Capabilities::QUATERNION) && received(&[Q0, Q1, Q2, Q3]) {
+            data.quaternion = Some([Q0, Q1, Q2, Q3].map(|q| reg(q) as f32 / 32768.0));
+        }
+
         data.update_flags = update_flags;
         data
     }
@@ -267,11 +628,17 @@ impl WitSensor {
     /// Read sensor data continuously
     pub fn read_sensor_data(&mut self) -> WitResult<SensorData> {
         // Request standard sensor data (accelerometer, gyroscope, angles)
-        self.read_registers(AX, DEFAULT_READ_COUNT)?;
-        
+        // Streaming protocols send this data on their own
+        if matches!(
+            self.protocol(),
+            WitProtocol::Modbus | WitProtocol::Modbus905x | WitProtocol::I2c
+        ) {
+            self.read_registers(AX, self.profile.read_count())?;
+        }
+
         // Wait a bit for response
         thread::sleep(Duration::from_millis(50));
-        
+
         // Process incoming data
         match self.process_incoming_data()? {
             Some(data) => Ok(data),
@@ -279,9 +646,148 @@ impl WitSensor {
         }
     }
 
-    /// Get the current baud rate
-    pub fn current_baud_rate(&self) -> u32 {
-        self.current_baud
+    /// Set the CAN bus baud rate of a CAN sensor (one of the CAN_BAUD_* values)
+    /// The sensor switches immediately, the host interface must follow
+    pub fn set_can_baud_rate(&mut self, baud_index: u16) -> WitResult<()> {
+        if !self.protocol().is_can() {
+            return Err(WitError::InvalidParameter(
+                "CAN baud rate can only be set on CAN sensors".to_string(),
+            ));
+        }
+        if baud_index > CAN_BAUD_3000 {
+            return Err(WitError::InvalidParameter(format!(
+                "Invalid CAN baud rate index: {}",
+                baud_index
+            )));
+        }
+
+        self.unlock()?;
+        self.write_register(BAUD, baud_index)
+    }
+
+    /// Select which packets a streaming sensor sends (RSW)
+    pub fn set_output_content(&mut self, content: OutputContent) -> WitResult<()> {
+        if content.is_empty() {
+            return Err(WitError::InvalidParameter("Output content cannot be empty".to_string()));
+        }
+        self.configure(RSW, content.register_value())
+    }
+
+    /// Read which packets a streaming sensor sends (RSW)
+    pub fn get_output_content(&mut self) -> WitResult<OutputContent> {
+        Ok(OutputContent::from_register(self.query_register(RSW)?))
+    }
+
+    /// Set the digital low-pass filter bandwidth
+    pub fn set_bandwidth(&mut self, bandwidth: Bandwidth) -> WitResult<()> {
+        self.set_setting(bandwidth)
+    }
+
+    /// Set how often a streaming sensor sends data
+    pub fn set_output_rate(&mut self, rate: OutputRate) -> WitResult<()> {
+        self.set_setting(rate)
+    }
+
+    /// Set the accelerometer full scale range
+    pub fn set_acc_range(&mut self, range: AccRange) -> WitResult<()> {
+        self.set_setting(range)
+    }
+
+    /// Set the gyroscope full scale range
+    pub fn set_gyro_range(&mut self, range: GyroRange) -> WitResult<()> {
+        self.set_setting(range)
+    }
+
+    /// Set how the sensor is mounted
+    pub fn set_orientation(&mut self, orientation: Orientation) -> WitResult<()> {
+        self.set_setting(orientation)
+    }
+
+    /// Choose between the 9-axis and 6-axis attitude algorithm
+    pub fn set_algorithm(&mut self, algorithm: AlgorithmAxes) -> WitResult<()> {
+        self.set_setting(algorithm)
+    }
+
+    /// Store the current configuration so it survives a power cycle
+    pub fn save(&mut self) -> WitResult<()> {
+        self.command(SAVE_PARAM)
+    }
+
+    /// Restore the factory configuration
+    /// This includes the baud rate and Modbus address, the host may have to follow
+    pub fn factory_reset(&mut self) -> WitResult<()> {
+        self.command(SAVE_DEFAULT)?;
+        self.ranges = MeasurementRanges::default();
+        Ok(())
+    }
+
+    /// Restart the sensor, unsaved configuration is lost
+    /// The cached ranges are kept if the sensor does not answer after restarting
+    pub fn reboot(&mut self) -> WitResult<()> {
+        self.command(SAVE_SWRST)?;
+        // Drop whatever the sensor sent while restarting
+        thread::sleep(Duration::from_millis(100));
+        self.transport.clear_input()?;
+
+        // Unsaved ranges are lost too, but a sensor still booting or back at a
+        // saved baud rate or address has not failed to reboot
+        match self.refresh_ranges() {
+            Ok(_) | Err(WitError::Timeout) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write a typed setting under unlock, verify and save it
+    pub fn set_setting<V: RegisterValue>(&mut self, value: V) -> WitResult<()> {
+        self.configure(V::REGISTER, value.into())
+    }
+
+    /// Apply a single configuration write as a verified transaction
+    fn configure(&mut self, register: u16, value: u16) -> WitResult<()> {
+        self.check_supported(register)?;
+
+        let mut transaction = ConfigTransaction::new();
+        transaction.write(register, value).on_mismatch(MismatchPolicy::SkipSave);
+        transaction.commit(self)
+    }
+
+    /// Refuse registers that do not exist with their usual meaning on the model
+    pub(crate) fn check_supported(&self, register: u16) -> WitResult<()> {
+        match Register::from_address(register) {
+            Some(known) if !self.profile.supports(known) => Err(WitError::InvalidParameter(format!(
+                "{} is not supported by {}",
+                known, self.profile.model
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Unlock the configuration and write a SAVE command
+    fn command(&mut self, command: u16) -> WitResult<()> {
+        if !self.profile.has(Capabilities::REGISTER_ACCESS) {
+            return Err(WitError::InvalidParameter(format!(
+                "{} does not support configuration commands",
+                self.profile.model
+            )));
+        }
+
+        self.unlock()?;
+        self.write_register(SAVE, command)
+    }
+
+    /// Get the protocol used to talk to the sensor
+    pub fn protocol(&self) -> WitProtocol {
+        self.transport.protocol()
+    }
+
+    /// Get the transport used to talk to the sensor
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Get the transport used to talk to the sensor mutably
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
     }
 
     /// Get a register value by address
@@ -293,4 +799,265 @@ impl WitSensor {
     pub fn get_all_registers(&self) -> &HashMap<u16, i16> {
         &self.registers
     }
+
+    /// Get the register cache mutably, so a bus can swap in the cache of each slave
+    pub(crate) fn registers_mut(&mut self) -> &mut HashMap<u16, i16> {
+        &mut self.registers
+    }
+}
+
+/// Convert a WT905x high precision angle register pair to degrees
+pub fn angle_905x(low: i16, high: i16) -> f32 {
+    register_pair(low, high) as f32 * ANGLE_905X_SCALE
+}
+
+/// Combine a low/high register pair into a 32-bit value
+pub fn register_pair(low: i16, high: i16) -> i32 {
+    ((high as i32) << 16) | (low as u16 as i32)
+}
+
+/// Convert a GPS coordinate in ddmm.mmmmm × 100000 to degrees
+fn gps_degrees(raw: i32) -> f64 {
+    let value = raw as f64 / 100000.0;
+    let degrees = (value / 100.0).trunc();
+    degrees + (value - degrees * 100.0) / 60.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockTransport;
+
+    /// Register pair of a 32-bit angle in millidegrees
+    fn pair(millidegrees: i32) -> (i16, i16) {
+        (millidegrees as i16, (millidegrees >> 16) as i16)
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_905x_angles() {
+        let cases = [(0, 0.0), (1, 0.001), (-1, -0.001), (90_500, 90.5), (-90_500, -90.5), (179_999, 179.999)];
+        for (millidegrees, degrees) in cases {
+            let (low, high) = pair(millidegrees);
+            assert_close(angle_905x(low, high), degrees);
+        }
+        // -180° is 0xFFFD40E0, the low word alone looks positive
+        assert_eq!(pair(-180_000), (0x40E0, -3));
+        assert_close(angle_905x(0x40E0, -3), -180.0);
+    }
+
+    #[test]
+    fn test_905x_readings_use_32_bit_angles_and_temp905x() {
+        let mut transport = MockTransport::new(WitProtocol::Modbus905x);
+        for (low, millidegrees) in [(LROLL, -1500), (LPITCH, 45_250), (LYAW, -170_000)] {
+            let (l, h) = pair(millidegrees);
+            transport = transport.with(low, l as u16).with(low + 1, h as u16);
+        }
+        // TEMP905X holds the temperature, TEMP is overlapped by the pitch
+        let transport = transport.with(TEMP905X, 2512);
+
+        let mut sensor = WitSensor::with_transport(transport);
+        let data = sensor.read_sensor_data().unwrap();
+        for (angle, expected) in data.angles.into_iter().zip([-1.5, 45.25, -170.0]) {
+            assert_close(angle, expected);
+        }
+        assert_eq!(data.temperature, 25.12);
+        assert!(data.has_angle_update());
+    }
+
+    type Action = fn(&mut WitSensor<MockTransport>) -> WitResult<()>;
+
+    fn modbus_sensor() -> WitSensor<MockTransport> {
+        let transport = MockTransport::new(WitProtocol::Modbus)
+            .with(ACCRANGE, AccRange::G16.into())
+            .with(GYRORANGE, GyroRange::Dps2000.into());
+        WitSensor::with_transport(transport)
+    }
+
+    #[test]
+    fn test_setters_write_verify_then_save() {
+        let cases: [(Action, u16, u16); 6] = [
+            (|s| s.set_bandwidth(Bandwidth::Hz21), BANDWIDTH, 4),
+            (|s| s.set_output_rate(OutputRate::Hz10), RRATE, RRATE_10HZ),
+            (|s| s.set_acc_range(AccRange::G4), ACCRANGE, 1),
+            (|s| s.set_gyro_range(GyroRange::Dps500), GYRORANGE, 1),
+            (|s| s.set_orientation(Orientation::Vertical), ORIENT, 1),
+            (|s| s.set_algorithm(AlgorithmAxes::SixAxis), AXIS6, ALGRITHM6),
+        ];
+        for (set, register, value) in cases {
+            let mut sensor = modbus_sensor();
+            set(&mut sensor).unwrap();
+            let transport = sensor.transport();
+            assert_eq!(transport.writes_after_unlock().unwrap(), [(register, value), (SAVE, SAVE_PARAM)]);
+            assert_eq!(transport.saved[&register], value);
+        }
+    }
+
+    #[test]
+    fn test_setting_mismatch_skips_save() {
+        let mut sensor = modbus_sensor();
+        sensor.transport_mut().read_only.push(ACCRANGE);
+
+        assert!(matches!(sensor.set_acc_range(AccRange::G4), Err(WitError::VerificationFailed(_))));
+        assert!(!sensor.transport().writes.iter().any(|&(register, _)| register == SAVE));
+        // Scaling follows what the sensor read back, not what was written
+        assert_eq!(sensor.ranges().acc, AccRange::G16);
+    }
+
+    #[test]
+    fn test_commands_write_save_values() {
+        let cases: [(Action, u16); 3] = [
+            (|s| s.save(), SAVE_PARAM),
+            (|s| s.factory_reset(), SAVE_DEFAULT),
+            (|s| s.reboot(), SAVE_SWRST),
+        ];
+        for (command, value) in cases {
+            let mut sensor = modbus_sensor();
+            command(&mut sensor).unwrap();
+            assert_eq!(sensor.transport().writes_after_unlock().unwrap(), [(SAVE, value)]);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_settings_are_refused() {
+        let mut sensor = modbus_sensor();
+        sensor.set_profile(&DeviceProfile::JY61);
+
+        assert!(matches!(sensor.set_bandwidth(Bandwidth::Hz21), Err(WitError::InvalidParameter(_))));
+        assert!(matches!(sensor.set_algorithm(AlgorithmAxes::SixAxis), Err(WitError::InvalidParameter(_))));
+        assert!(matches!(sensor.save(), Err(WitError::InvalidParameter(_))));
+        assert!(matches!(sensor.factory_reset(), Err(WitError::InvalidParameter(_))));
+        assert!(sensor.transport().writes.is_empty());
+    }
+
+    fn serial_sensor() -> WitSensor<MockTransport> {
+        let mut transport = MockTransport::new(WitProtocol::Modbus).with(BAUD, WIT_BAUD_9600);
+        transport.baud = Some(9600);
+        WitSensor::with_transport(transport)
+    }
+
+    #[test]
+    fn test_baud_change_follows_the_sensor() {
+        let mut sensor = serial_sensor();
+        sensor.change_baud_rate(BaudRate::B115200).unwrap();
+
+        let transport = sensor.transport();
+        assert_eq!(transport.baud, Some(115200));
+        assert_eq!(transport.saved[&BAUD], WIT_BAUD_115200);
+        assert_eq!(
+            transport.writes,
+            [(KEY, KEY_UNLOCK), (BAUD, WIT_BAUD_115200), (KEY, KEY_UNLOCK), (SAVE, SAVE_PARAM)]
+        );
+    }
+
+    #[test]
+    fn test_baud_change_falls_back_when_the_sensor_stays() {
+        let mut sensor = serial_sensor();
+        sensor.transport_mut().read_only.push(BAUD);
+
+        let error = sensor.change_baud_rate(BaudRate::B115200).unwrap_err();
+        let WitError::VerificationFailed(mismatches) = error else {
+            panic!("unexpected error: {}", error);
+        };
+        assert_eq!(mismatches, [RegisterMismatch { register: BAUD, expected: WIT_BAUD_115200, actual: None }]);
+
+        // Back at the rate the sensor still uses, nothing saved
+        let transport = sensor.transport();
+        assert_eq!(transport.baud, Some(9600));
+        assert!(!transport.writes.iter().any(|&(register, _)| register == SAVE));
+        assert_eq!(sensor.query_register(BAUD).unwrap(), WIT_BAUD_9600);
+    }
+
+    #[test]
+    fn test_baud_rates_above_230400_are_refused() {
+        let mut sensor = serial_sensor();
+        for baud in [BaudRate::B460800, BaudRate::B921600] {
+            assert!(matches!(sensor.change_baud_rate(baud), Err(WitError::InvalidParameter(_))));
+        }
+        assert!(sensor.transport().writes.is_empty());
+
+        // Staying at the current rate needs no writes either
+        sensor.change_baud_rate(BaudRate::B9600).unwrap();
+        assert!(sensor.transport().writes.is_empty());
+    }
+
+    #[test]
+    fn test_reboot_refreshes_ranges_if_the_sensor_answers() {
+        let changed = MeasurementRanges {
+            acc: AccRange::G4,
+            gyro: GyroRange::Dps500,
+        };
+
+        // The unsaved ranges are lost
+        let mut sensor = modbus_sensor();
+        sensor.set_ranges(changed);
+        sensor.reboot().unwrap();
+        assert_eq!(sensor.ranges(), MeasurementRanges::default());
+
+        // Back at a saved address the sensor no longer answers, the cached ranges stay
+        let mut sensor = addressed_sensor();
+        sensor.transport_mut().saved.insert(IICADDR, 0x52);
+        sensor.set_ranges(changed);
+        sensor.reboot().unwrap();
+        assert_eq!(sensor.ranges(), changed);
+        assert_eq!(sensor.transport().get(IICADDR), 0x52);
+    }
+
+    /// Sensor at the factory address, with a neighbour at 0x51
+    fn addressed_sensor() -> WitSensor<MockTransport> {
+        let mut transport = MockTransport::new(WitProtocol::Modbus).with(IICADDR, 0x50);
+        transport.address = Some(0x50);
+        transport.neighbours.push(0x51);
+        WitSensor::with_transport(transport)
+    }
+
+    #[test]
+    fn test_address_change_follows_the_sensor() {
+        let mut sensor = addressed_sensor();
+        sensor.change_address(0x52).unwrap();
+
+        let transport = sensor.transport();
+        assert_eq!(transport.address, Some(0x52));
+        assert_eq!(transport.saved[&IICADDR], 0x52);
+        assert_eq!(transport.writes, [(KEY, KEY_UNLOCK), (IICADDR, 0x52), (KEY, KEY_UNLOCK), (SAVE, SAVE_PARAM)]);
+    }
+
+    #[test]
+    fn test_address_in_use_is_refused() {
+        let mut sensor = addressed_sensor();
+        assert!(matches!(sensor.change_address(0x51), Err(WitError::AddressInUse(0x51))));
+
+        let transport = sensor.transport();
+        assert_eq!(transport.address, Some(0x50));
+        assert!(transport.writes.is_empty());
+    }
+
+    #[test]
+    fn test_address_change_through_broadcast_is_refused() {
+        let mut sensor = addressed_sensor();
+        sensor.transport_mut().address = Some(BROADCAST_ADDRESS);
+
+        assert!(matches!(sensor.change_address(0x52), Err(WitError::InvalidParameter(_))));
+        assert!(matches!(sensor.change_address(0), Err(WitError::InvalidParameter(_))));
+        assert!(sensor.transport().writes.is_empty());
+    }
+
+    #[test]
+    fn test_address_change_falls_back_when_the_sensor_stays() {
+        let mut sensor = addressed_sensor();
+        sensor.transport_mut().read_only.push(IICADDR);
+
+        let error = sensor.change_address(0x52).unwrap_err();
+        let WitError::VerificationFailed(mismatches) = error else {
+            panic!("unexpected error: {}", error);
+        };
+        assert_eq!(mismatches, [RegisterMismatch { register: IICADDR, expected: 0x52, actual: None }]);
+
+        let transport = sensor.transport();
+        assert_eq!(transport.address, Some(0x50));
+        assert!(!transport.writes.iter().any(|&(register, _)| register == SAVE));
+    }
 }
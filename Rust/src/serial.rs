@@ -2,6 +2,9 @@ use crate::error::{WitError, WitResult};
 use serial2::SerialPort;
 use std::time::Duration;
 
+/// Read timeout of a freshly opened port
+pub const DEFAULT_READ_TIMEOUT: Duration = Duration::from_millis(100);
+
 /// Serial communication wrapper for WitMotion sensors
 pub struct WitSerial {
     port: SerialPort,
@@ -11,10 +14,22 @@ pub struct WitSerial {
 impl WitSerial {
     /// Open a serial port with the specified device path and baud rate
     pub fn open(device_path: &str, baud_rate: u32) -> WitResult<Self> {
-        let mut port = SerialPort::open(device_path, baud_rate)?;
-        
+        let port = SerialPort::open(device_path, baud_rate)?;
+        Self::with_port(port, baud_rate)
+    }
+
+    /// Open a connected pseudo-terminal pair, the other end plays the sensor
+    #[cfg(all(test, unix))]
+    pub(crate) fn pair() -> WitResult<(Self, SerialPort)> {
+        let (port, other) = SerialPort::pair()?;
+        let mut serial = Self::with_port(port, 9600)?;
+        serial.set_baud_rate(9600)?;
+        Ok((serial, other))
+    }
+
+    fn with_port(mut port: SerialPort, baud_rate: u32) -> WitResult<Self> {
         // Set timeouts
-        port.set_read_timeout(Duration::from_millis(100))?;
+        port.set_read_timeout(DEFAULT_READ_TIMEOUT)?;
         port.set_write_timeout(Duration::from_millis(100))?;
 
         Ok(Self {
@@ -45,23 +60,29 @@ impl WitSerial {
 
     /// Change the baud rate of the serial port
     pub fn set_baud_rate(&mut self, baud_rate: u32) -> WitResult<()> {
-        // For serial2, we need to recreate the port with new baud rate
-        // This is a limitation of the serial2 crate
-        let device_name = format!("/dev/ttyUSB0"); // We'll need to store the device path
-        let mut new_port = SerialPort::open(&device_name, baud_rate)?;
-        new_port.set_read_timeout(Duration::from_millis(100))?;
-        new_port.set_write_timeout(Duration::from_millis(100))?;
-        
-        self.port = new_port;
+        let mut settings = self.port.get_configuration()?;
+        settings.set_baud_rate(baud_rate)?;
+        self.port.set_configuration(&settings)?;
         self.current_baud = baud_rate;
         Ok(())
     }
 
+    /// Change how long a read waits for data
+    pub fn set_read_timeout(&mut self, timeout: Duration) -> WitResult<()> {
+        Ok(self.port.set_read_timeout(timeout)?)
+    }
+
     /// Get the current baud rate
     pub fn baud_rate(&self) -> u32 {
         self.current_baud
     }
 
+    /// Time needed to transmit the given number of bytes at the current baud rate
+    /// Assumes 10 bits per byte (start + 8 data + stop)
+    pub fn transmission_time(&self, bytes: usize) -> Duration {
+        Duration::from_micros(1_000_000 * bytes as u64 * 10 / self.current_baud as u64)
+    }
+
     /// Read a single byte from the serial port
     pub fn read_byte(&mut self) -> WitResult<Option<u8>> {
         let mut buffer = [0u8; 1];
@@ -0,0 +1,357 @@
+//! Typed values of the configuration registers
+
+use crate::{
+    error::{WitError, WitResult},
+    registers::*,
+};
+use bitflags::bitflags;
+use std::{fmt, str::FromStr};
+
+/// A typed value of a configuration register
+pub trait RegisterValue: TryFrom<u16, Error = WitError> + Into<u16> + Copy {
+    /// Register the value is stored in
+    const REGISTER: u16;
+}
+
+macro_rules! register_values {
+    (
+        $(#[$meta:meta])*
+        $name:ident in $register:ident {
+            $($(#[$variant_meta:meta])* $variant:ident = $value:expr => $label:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+        }
+
+        impl $name {
+            /// All values in register order
+            pub const ALL: &'static [$name] = &[$($name::$variant,)*];
+        }
+
+        impl RegisterValue for $name {
+            const REGISTER: u16 = $register;
+        }
+
+        impl TryFrom<u16> for $name {
+            type Error = WitError;
+
+            fn try_from(value: u16) -> WitResult<Self> {
+                match value {
+                    $(v if v == $value => Ok($name::$variant),)*
+                    _ => Err(WitError::InvalidParameter(format!(
+                        "Invalid {} value: {}",
+                        stringify!($name),
+                        value
+                    ))),
+                }
+            }
+        }
+
+        impl From<$name> for u16 {
+            fn from(value: $name) -> u16 {
+                match value {
+                    $($name::$variant => $value,)*
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $($name::$variant => write!(f, $label),)*
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = WitError;
+
+            /// Parse a label, ignoring case, spaces and "±" ("21 Hz", "16g", "9-axis")
+            fn from_str(s: &str) -> WitResult<Self> {
+                let key = label_key(s);
+                Self::ALL
+                    .iter()
+                    .copied()
+                    .find(|value| label_key(&value.to_string()) == key)
+                    .ok_or_else(|| WitError::InvalidParameter(format!(
+                        "Invalid {}: {}",
+                        stringify!($name),
+                        s
+                    )))
+            }
+        }
+    };
+}
+
+/// Normalize a value label for comparison
+fn label_key(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '±')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+register_values! {
+    /// Serial baud rate (BAUD)
+    BaudRate in BAUD {
+        B4800 = WIT_BAUD_4800 => "4800 baud",
+        B9600 = WIT_BAUD_9600 => "9600 baud",
+        B19200 = WIT_BAUD_19200 => "19200 baud",
+        B38400 = WIT_BAUD_38400 => "38400 baud",
+        B57600 = WIT_BAUD_57600 => "57600 baud",
+        B115200 = WIT_BAUD_115200 => "115200 baud",
+        B230400 = WIT_BAUD_230400 => "230400 baud",
+        B460800 = WIT_BAUD_460800 => "460800 baud",
+        B921600 = WIT_BAUD_921600 => "921600 baud",
+    }
+}
+
+impl BaudRate {
+    /// Line speed in bits per second
+    pub fn bits_per_second(&self) -> u32 {
+        match self {
+            BaudRate::B4800 => 4800,
+            BaudRate::B9600 => 9600,
+            BaudRate::B19200 => 19200,
+            BaudRate::B38400 => 38400,
+            BaudRate::B57600 => 57600,
+            BaudRate::B115200 => 115200,
+            BaudRate::B230400 => 230400,
+            BaudRate::B460800 => 460800,
+            BaudRate::B921600 => 921600,
+        }
+    }
+
+    /// Find the setting for a line speed
+    pub fn from_bits_per_second(bits_per_second: u32) -> WitResult<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|baud| baud.bits_per_second() == bits_per_second)
+            .ok_or_else(|| WitError::InvalidParameter(format!("Unsupported baud rate: {}", bits_per_second)))
+    }
+}
+
+register_values! {
+    /// Data output rate of streaming sensors (RRATE)
+    OutputRate in RRATE {
+        Hz0_2 = RRATE_02HZ => "0.2 Hz",
+        Hz0_5 = RRATE_05HZ => "0.5 Hz",
+        Hz1 = RRATE_1HZ => "1 Hz",
+        Hz2 = RRATE_2HZ => "2 Hz",
+        Hz5 = RRATE_5HZ => "5 Hz",
+        Hz10 = RRATE_10HZ => "10 Hz",
+        Hz20 = RRATE_20HZ => "20 Hz",
+        Hz50 = RRATE_50HZ => "50 Hz",
+        Hz100 = RRATE_100HZ => "100 Hz",
+        /// Only supported by WT931
+        Hz125 = RRATE_125HZ => "125 Hz",
+        Hz200 = RRATE_200HZ => "200 Hz",
+        /// Output a single set of packets
+        Once = RRATE_ONCE => "once",
+        /// No output
+        Off = RRATE_NONE => "off",
+    }
+}
+
+impl OutputRate {
+    /// Output frequency in Hz, None for single shot or no output
+    pub fn hz(&self) -> Option<f32> {
+        match self {
+            OutputRate::Hz0_2 => Some(0.2),
+            OutputRate::Hz0_5 => Some(0.5),
+            OutputRate::Hz1 => Some(1.0),
+            OutputRate::Hz2 => Some(2.0),
+            OutputRate::Hz5 => Some(5.0),
+            OutputRate::Hz10 => Some(10.0),
+            OutputRate::Hz20 => Some(20.0),
+            OutputRate::Hz50 => Some(50.0),
+            OutputRate::Hz100 => Some(100.0),
+            OutputRate::Hz125 => Some(125.0),
+            OutputRate::Hz200 => Some(200.0),
+            OutputRate::Once | OutputRate::Off => None,
+        }
+    }
+}
+
+register_values! {
+    /// Digital low-pass filter bandwidth (BANDWIDTH)
+    Bandwidth in BANDWIDTH {
+        Hz256 = 0 => "256 Hz",
+        Hz184 = 1 => "184 Hz",
+        Hz94 = 2 => "94 Hz",
+        Hz44 = 3 => "44 Hz",
+        Hz21 = 4 => "21 Hz",
+        Hz10 = 5 => "10 Hz",
+        Hz5 = 6 => "5 Hz",
+    }
+}
+
+impl Bandwidth {
+    /// Filter bandwidth in Hz
+    pub fn hz(&self) -> u16 {
+        match self {
+            Bandwidth::Hz256 => 256,
+            Bandwidth::Hz184 => 184,
+            Bandwidth::Hz94 => 94,
+            Bandwidth::Hz44 => 44,
+            Bandwidth::Hz21 => 21,
+            Bandwidth::Hz10 => 10,
+            Bandwidth::Hz5 => 5,
+        }
+    }
+}
+
+register_values! {
+    /// Accelerometer full scale range (ACCRANGE)
+    AccRange in ACCRANGE {
+        G2 = 0 => "±2 g",
+        G4 = 1 => "±4 g",
+        G8 = 2 => "±8 g",
+        G16 = 3 => "±16 g",
+    }
+}
+
+impl AccRange {
+    /// Full scale in g
+    pub fn g(&self) -> f32 {
+        match self {
+            AccRange::G2 => 2.0,
+            AccRange::G4 => 4.0,
+            AccRange::G8 => 8.0,
+            AccRange::G16 => 16.0,
+        }
+    }
+
+    /// Factor converting a raw acceleration register to g
+    pub fn scale(&self) -> f32 {
+        self.g() / 32768.0
+    }
+}
+
+register_values! {
+    /// Gyroscope full scale range (GYRORANGE)
+    GyroRange in GYRORANGE {
+        Dps250 = 0 => "±250 °/s",
+        Dps500 = 1 => "±500 °/s",
+        Dps1000 = 2 => "±1000 °/s",
+        Dps2000 = 3 => "±2000 °/s",
+    }
+}
+
+impl GyroRange {
+    /// Full scale in degrees per second
+    pub fn degrees_per_second(&self) -> f32 {
+        match self {
+            GyroRange::Dps250 => 250.0,
+            GyroRange::Dps500 => 500.0,
+            GyroRange::Dps1000 => 1000.0,
+            GyroRange::Dps2000 => 2000.0,
+        }
+    }
+
+    /// Factor converting a raw angular rate register to °/s
+    pub fn scale(&self) -> f32 {
+        self.degrees_per_second() / 32768.0
+    }
+}
+
+register_values! {
+    /// Mounting orientation (ORIENT)
+    Orientation in ORIENT {
+        Horizontal = 0 => "horizontal",
+        Vertical = 1 => "vertical",
+    }
+}
+
+register_values! {
+    /// Attitude algorithm (AXIS6)
+    AlgorithmAxes in AXIS6 {
+        /// Accelerometer, gyroscope and magnetometer
+        NineAxis = ALGRITHM9 => "9-axis",
+        /// Accelerometer and gyroscope only, yaw drifts
+        SixAxis = ALGRITHM6 => "6-axis",
+    }
+}
+
+register_values! {
+    /// Calibration mode (CALSW)
+    CalibrationMode in CALSW {
+        /// Normal operation, ends a calibration
+        Normal = NORMAL => "normal",
+        GyroAcc = CALGYROACC => "gyroscope and accelerometer",
+        Magnetic = CALMAG => "magnetometer",
+        Altitude = CALALTITUDE => "altitude reset",
+        AngleZ = CALANGLEZ => "yaw reset",
+        AccLeft = CALACCL => "accelerometer left",
+        AccRight = CALACCR => "accelerometer right",
+        MagMinMax = CALMAGMM => "magnetometer min/max",
+        ReferenceAngle = CALREFANGLE => "reference angle",
+        MagTwoStep = CALMAG2STEP => "magnetometer two step",
+        Hexahedron = CALHEXAHEDRON => "hexahedron",
+    }
+}
+
+bitflags! {
+    /// Packets a streaming sensor sends (RSW)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct OutputContent: u16 {
+        const TIME = RSW_TIME;           // 0x50 time
+        const ACC = RSW_ACC;             // 0x51 acceleration
+        const GYRO = RSW_GYRO;           // 0x52 angular rate
+        const ANGLE = RSW_ANGLE;         // 0x53 angles
+        const MAG = RSW_MAG;             // 0x54 magnetic field
+        const PORT = RSW_PORT;           // 0x55 port status
+        const PRESSURE = RSW_PRESS;      // 0x56 pressure and height
+        const GPS = RSW_GPS;             // 0x57 longitude and latitude
+        const VELOCITY = RSW_V;          // 0x58 GPS height, yaw and speed
+        const QUATERNION = RSW_Q;        // 0x59 quaternion
+        const GSA = RSW_GSA;             // 0x5A satellite accuracy
+    }
+}
+
+impl OutputContent {
+    /// Content selected by an RSW register value, keeping bits this crate does not name
+    pub fn from_register(value: u16) -> Self {
+        Self::from_bits_retain(value & RSW_MASK)
+    }
+
+    /// RSW register value selecting this content
+    pub fn register_value(&self) -> u16 {
+        self.bits() & RSW_MASK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(BaudRate::B115200.bits_per_second(), 115200);
+        assert_eq!(u16::from(BaudRate::B115200), WIT_BAUD_115200);
+        assert_eq!(BaudRate::try_from(2).unwrap(), BaudRate::B9600);
+        assert_eq!(BaudRate::from_bits_per_second(38400).unwrap(), BaudRate::B38400);
+        assert!(BaudRate::try_from(0).is_err());
+
+        assert_eq!(OutputRate::try_from(RRATE_NONE).unwrap(), OutputRate::Off);
+        assert_eq!(OutputRate::Hz0_2.hz(), Some(0.2));
+        assert_eq!(GyroRange::try_from(3).unwrap().to_string(), "±2000 °/s");
+        assert_eq!(AccRange::G16.scale(), 16.0 / 32768.0);
+        assert!(Bandwidth::try_from(7).is_err());
+        assert_eq!(CalibrationMode::try_from(0x12).unwrap(), CalibrationMode::Hexahedron);
+        assert_eq!(AlgorithmAxes::REGISTER, AXIS6);
+        assert_eq!("21hz".parse::<Bandwidth>().unwrap(), Bandwidth::Hz21);
+        assert_eq!("16 G".parse::<AccRange>().unwrap(), AccRange::G16);
+        assert_eq!("Once".parse::<OutputRate>().unwrap(), OutputRate::Once);
+        assert!("12 Hz".parse::<OutputRate>().is_err());
+
+        let content = OutputContent::ACC | OutputContent::GYRO | OutputContent::ANGLE;
+        assert_eq!(content.register_value(), 0x0E);
+        assert_eq!(OutputContent::from_register(0xF80E).register_value(), 0x80E);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    can::{CanBus, CanFrame, CanTransport, CAN_MAX_DATA_LENGTH},
+    error::{WitError, WitResult},
+    sensor::WitSensor,
+};
+use std::{
+    ffi::CString,
+    io,
+    mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    time::Duration,
+};
+
+/// Linux SocketCAN interface (e.g. `can0` or `vcan0`)
+pub struct WitCanSocket {
+    fd: OwnedFd,
+}
+
+impl WitCanSocket {
+    /// Open a raw CAN socket bound to the given network interface
+    pub fn open(interface: &str) -> WitResult<Self> {
+        let name = CString::new(interface)
+            .map_err(|_| WitError::InvalidParameter(format!("Invalid CAN interface: {}", interface)))?;
+
+        // SAFETY: plain libc calls, the returned descriptor is owned right away
+        let fd = unsafe { libc::socket(libc::PF_CAN, libc::SOCK_RAW, libc::CAN_RAW) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if index == 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        // SAFETY: sockaddr_can is plain old data, zeroed is a valid value
+        let mut address: libc::sockaddr_can = unsafe { mem::zeroed() };
+        address.can_family = libc::AF_CAN as libc::sa_family_t;
+        address.can_ifindex = index as libc::c_int;
+
+        let result = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &address as *const libc::sockaddr_can as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_can>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+
+        let mut socket = Self { fd };
+        socket.set_read_timeout(Duration::from_millis(100))?;
+        Ok(socket)
+    }
+
+    /// Set how long `receive` waits for a frame
+    pub fn set_read_timeout(&mut self, timeout: Duration) -> WitResult<()> {
+        let timeval = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+
+        let result = unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeval as *const libc::timeval as *const libc::c_void,
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+impl CanBus for WitCanSocket {
+    fn send(&mut self, frame: &CanFrame) -> WitResult<()> {
+        if frame.data.len() > CAN_MAX_DATA_LENGTH {
+            return Err(WitError::InvalidParameter("CAN payload too long".to_string()));
+        }
+
+        // SAFETY: can_frame is plain old data, zeroed is a valid value
+        let mut raw: libc::can_frame = unsafe { mem::zeroed() };
+        raw.can_id = if frame.extended {
+            (frame.id & libc::CAN_EFF_MASK) | libc::CAN_EFF_FLAG
+        } else {
+            frame.id & libc::CAN_SFF_MASK
+        };
+        raw.can_dlc = frame.data.len() as u8;
+        raw.data[..frame.data.len()].copy_from_slice(&frame.data);
+
+        let written = unsafe {
+            libc::write(
+                self.fd.as_raw_fd(),
+                &raw as *const libc::can_frame as *const libc::c_void,
+                mem::size_of::<libc::can_frame>(),
+            )
+        };
+        if written < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> WitResult<Option<CanFrame>> {
+        loop {
+            // SAFETY: can_frame is plain old data, zeroed is a valid value
+            let mut raw: libc::can_frame = unsafe { mem::zeroed() };
+            let read = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    &mut raw as *mut libc::can_frame as *mut libc::c_void,
+                    mem::size_of::<libc::can_frame>(),
+                )
+            };
+
+            if read < 0 {
+                let error = io::Error::last_os_error();
+                return match error.kind() {
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Ok(None),
+                    io::ErrorKind::Interrupted => continue,
+                    _ => Err(error.into()),
+                };
+            }
+
+            // Skip error and remote frames, the sensor only sends data frames
+            if raw.can_id & (libc::CAN_ERR_FLAG | libc::CAN_RTR_FLAG) != 0 {
+                continue;
+            }
+
+            let extended = raw.can_id & libc::CAN_EFF_FLAG != 0;
+            let id = if extended {
+                raw.can_id & libc::CAN_EFF_MASK
+            } else {
+                raw.can_id & libc::CAN_SFF_MASK
+            };
+            let length = (raw.can_dlc as usize).min(CAN_MAX_DATA_LENGTH);
+
+            return Ok(Some(CanFrame {
+                id,
+                extended,
+                data: raw.data[..length].to_vec(),
+            }));
+        }
+    }
+}
+
+impl WitSensor<CanTransport<WitCanSocket>> {
+    /// Open a CAN sensor on a SocketCAN interface
+    /// `high_precision` selects the WT905x 32-bit angle frames
+    pub fn open_socketcan(interface: &str, can_id: u32, high_precision: bool) -> WitResult<Self> {
+        let socket = WitCanSocket::open(interface)?;
+        Ok(Self::with_transport(CanTransport::new(socket, can_id, high_precision)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registers::*;
+
+    /// Needs a virtual CAN interface:
+    /// `ip link add dev vcan0 type vcan && ip link set up vcan0`
+    #[test]
+    #[ignore]
+    fn test_vcan_round_trip() {
+        let mut sensor = WitSensor::open_socketcan("vcan0", 0x50, false).unwrap();
+        let mut device = WitCanSocket::open("vcan0").unwrap();
+
+        sensor.read_registers(BANDWIDTH, 3).unwrap();
+        let request = device.receive().unwrap().unwrap();
+        assert_eq!(request.id, 0x50);
+        assert_eq!(request.data, vec![0xFF, 0xAA, 0x27, 0x1F, 0x00]);
+
+        let reply = CanFrame::new(0x50, &[0x55, 0x5F, 0x04, 0x00, 0x03, 0x00, 0x03, 0x00]).unwrap();
+        let angle = CanFrame::new(0x50, &[0x55, 0x53, 0x00, 0x40, 0x00, 0xC0, 0x00, 0x20]).unwrap();
+        device.send(&reply).unwrap();
+        device.send(&angle).unwrap();
+
+        let data = sensor.process_incoming_data().unwrap().unwrap();
+        assert!(data.has_angle_update());
+        assert_eq!(data.angles, [90.0, -90.0, 45.0]);
+        assert_eq!(sensor.get_register(BANDWIDTH), Some(4));
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{
+    error::{WitError, WitResult},
+    registers::*,
+    sensor::WitSensor,
+    transport::Transport,
+};
+use std::{fmt, thread, time::Duration};
+
+/// Time the sensor needs to apply a configuration write
+const WRITE_DELAY: Duration = Duration::from_millis(20);
+
+/// A register that read back a different value than was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterMismatch {
+    pub register: u16,
+    pub expected: u16,
+    /// Value read back, None if the sensor did not answer
+    pub actual: Option<u16>,
+}
+
+impl fmt::Display for RegisterMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = register_name(self.register);
+        match self.actual {
+            Some(actual) => write!(f, "{} (wrote 0x{:04X}, read 0x{:04X})", name, self.expected, actual),
+            None => write!(f, "{} (wrote 0x{:04X}, no answer)", name, self.expected),
+        }
+    }
+}
+
+/// What to do when a written register does not read back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MismatchPolicy {
+    /// Write the previous values back, then skip the save
+    #[default]
+    RollBack,
+    /// Keep the new values until the next power cycle, but skip the save
+    SkipSave,
+}
+
+/// A batch of configuration writes applied as one unit
+///
+/// Committing unlocks the configuration (KEY = 0xB588), writes each register,
+/// reads the readable ones back and only then saves (SAVE = 0) so the new
+/// values survive a power cycle. Registers that change the link itself, like
+/// BAUD or IICADDR, cannot be read back over the old link and do not belong here.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigTransaction {
+    writes: Vec<(u16, u16)>,
+    policy: MismatchPolicy,
+}
+
+impl ConfigTransaction {
+    /// Create an empty transaction that rolls back on mismatches
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a register write, replacing an earlier write to the same register
+    pub fn write(&mut self, register: u16, value: u16) -> &mut Self {
+        match self.writes.iter_mut().find(|(r, _)| *r == register) {
+            Some(write) => write.1 = value,
+            None => self.writes.push((register, value)),
+        }
+        self
+    }
+
+    /// Choose what happens when verification fails
+    pub fn on_mismatch(&mut self, policy: MismatchPolicy) -> &mut Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Get the queued writes in order
+    pub fn writes(&self) -> &[(u16, u16)] {
+        &self.writes
+    }
+
+    /// Check whether there is nothing to write
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Apply the writes to a sensor, verify them and save
+    ///
+    /// Returns `WitError::VerificationFailed` listing the registers that did not
+    /// read back, in which case nothing is saved. With `MismatchPolicy::RollBack`
+    /// any error after the first write puts the previous values back; if some
+    /// cannot be written, `WitError::RollbackFailed` wraps the original error.
+    pub fn commit<T: Transport>(&self, sensor: &mut WitSensor<T>) -> WitResult<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        // Remember the old values so they can be restored
+        let mut previous = Vec::new();
+        if self.policy == MismatchPolicy::RollBack {
+            for &(register, _) in self.writes.iter().filter(|(r, _)| is_verifiable(*r)) {
+                previous.push((register, sensor.query_register(register)?));
+            }
+        }
+
+        sensor.unlock()?;
+        let Err(cause) = self.apply(sensor) else {
+            return Ok(());
+        };
+
+        // Keep going when a write fails, so as much as possible is restored
+        let failures: Vec<_> = previous
+            .iter()
+            .filter_map(|&(register, value)| write(sensor, register, value).err().map(|e| (register, e)))
+            .collect();
+        if failures.is_empty() {
+            Err(cause)
+        } else {
+            Err(WitError::RollbackFailed {
+                cause: Box::new(cause),
+                failures,
+            })
+        }
+    }
+
+    /// Write, read back and save once the configuration is unlocked
+    fn apply<T: Transport>(&self, sensor: &mut WitSensor<T>) -> WitResult<()> {
+        for &(register, value) in &self.writes {
+            write(sensor, register, value)?;
+        }
+
+        let mut mismatches = Vec::new();
+        for &(register, expected) in self.writes.iter().filter(|(r, _)| is_verifiable(*r)) {
+            let actual = match sensor.query_register(register) {
+                Ok(value) => Some(value),
+                Err(WitError::Timeout) => None,
+                Err(e) => return Err(e),
+            };
+            if actual != Some(expected) {
+                mismatches.push(RegisterMismatch { register, expected, actual });
+            }
+        }
+        if !mismatches.is_empty() {
+            return Err(WitError::VerificationFailed(mismatches));
+        }
+
+        write(sensor, SAVE, SAVE_PARAM)
+    }
+}
+
+/// Check whether a register holds what was written to it
+fn is_verifiable(register: u16) -> bool {
+    Register::from_address(register).is_some_and(|r| r.access().is_readable())
+}
+
+/// Write a register and give the sensor time to apply it
+fn write<T: Transport>(sensor: &mut WitSensor<T>, register: u16, value: u16) -> WitResult<()> {
+    sensor.write_register(register, value)?;
+    thread::sleep(WRITE_DELAY);
+    // Consume the write echo so it does not delay the next answer
+    sensor.process_incoming_data()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mock::MockTransport, protocol::WitProtocol};
+
+    fn sensor() -> WitSensor<MockTransport> {
+        let transport = MockTransport::new(WitProtocol::Modbus).with(BANDWIDTH, 1).with(RRATE, RRATE_1HZ);
+        WitSensor::with_transport(transport)
+    }
+
+    fn transaction(policy: MismatchPolicy) -> ConfigTransaction {
+        let mut transaction = ConfigTransaction::new();
+        transaction.write(BANDWIDTH, 4).write(RRATE, RRATE_10HZ).on_mismatch(policy);
+        transaction
+    }
+
+    #[test]
+    fn test_later_writes_replace_earlier_ones() {
+        let mut transaction = ConfigTransaction::new();
+        transaction.write(BANDWIDTH, 1).write(RRATE, RRATE_10HZ).write(BANDWIDTH, 4);
+
+        assert_eq!(transaction.writes(), &[(BANDWIDTH, 4), (RRATE, RRATE_10HZ)]);
+        assert!(is_verifiable(BANDWIDTH));
+        assert!(!is_verifiable(SAVE));
+    }
+
+    #[test]
+    fn test_commit_writes_under_unlock_then_saves() {
+        let mut sensor = sensor();
+        transaction(MismatchPolicy::RollBack).commit(&mut sensor).unwrap();
+
+        let transport = sensor.transport();
+        assert_eq!(
+            transport.writes_after_unlock().unwrap(),
+            [(BANDWIDTH, 4), (RRATE, RRATE_10HZ), (SAVE, SAVE_PARAM)]
+        );
+        assert_eq!(transport.saved[&BANDWIDTH], 4);
+    }
+
+    #[test]
+    fn test_mismatch_rolls_back_and_skips_save() {
+        let mut sensor = sensor();
+        sensor.transport_mut().read_only.push(RRATE);
+
+        let error = transaction(MismatchPolicy::RollBack).commit(&mut sensor).unwrap_err();
+        let WitError::VerificationFailed(mismatches) = error else {
+            panic!("unexpected error: {}", error);
+        };
+        assert_eq!(
+            mismatches,
+            [RegisterMismatch { register: RRATE, expected: RRATE_10HZ, actual: Some(RRATE_1HZ) }]
+        );
+
+        let transport = sensor.transport();
+        assert_eq!(transport.get(BANDWIDTH), 1);
+        assert!(!transport.writes.iter().any(|&(register, _)| register == SAVE));
+    }
+
+    #[test]
+    fn test_skip_save_keeps_new_values() {
+        let mut sensor = sensor();
+        sensor.transport_mut().read_only.push(RRATE);
+
+        let error = transaction(MismatchPolicy::SkipSave).commit(&mut sensor).unwrap_err();
+        assert!(matches!(error, WitError::VerificationFailed(_)));
+
+        let transport = sensor.transport();
+        assert_eq!(transport.get(BANDWIDTH), 4);
+        assert_eq!(transport.saved[&BANDWIDTH], 1);
+    }
+
+    #[test]
+    fn test_read_back_errors_roll_back() {
+        let mut sensor = sensor();
+        // The two reads of the old values succeed, the read back does not
+        sensor.transport_mut().reads_before_exception = Some(2);
+
+        let error = transaction(MismatchPolicy::RollBack).commit(&mut sensor).unwrap_err();
+        assert!(matches!(error, WitError::ModbusException { code: 0x02, .. }));
+
+        let transport = sensor.transport();
+        assert_eq!((transport.get(BANDWIDTH), transport.get(RRATE)), (1, RRATE_1HZ));
+        assert!(!transport.writes.iter().any(|&(register, _)| register == SAVE));
+    }
+
+    #[test]
+    fn test_failed_rollback_still_reports_mismatches() {
+        let mut sensor = sensor();
+        sensor.transport_mut().read_only.push(RRATE);
+        // KEY, BANDWIDTH and RRATE go through, the rollback writes do not
+        sensor.transport_mut().writes_before_error = Some(3);
+
+        let error = transaction(MismatchPolicy::RollBack).commit(&mut sensor).unwrap_err();
+        let WitError::RollbackFailed { cause, failures } = error else {
+            panic!("unexpected error: {}", error);
+        };
+        assert!(matches!(*cause, WitError::VerificationFailed(ref mismatches) if mismatches[0].register == RRATE));
+        let registers: Vec<_> = failures.iter().map(|&(register, _)| register).collect();
+        assert_eq!(registers, [BANDWIDTH, RRATE]);
+        assert_eq!(sensor.transport().get(BANDWIDTH), 4);
+    }
+}
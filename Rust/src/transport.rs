@@ -0,0 +1,280 @@
+use crate::{
+    error::{WitError, WitResult},
+    modbus::{ModbusProtocol, MAX_READ_COUNT},
+    normal::{NormalProtocol, PACKET_LENGTH, REGISTERS_PER_READ},
+    planner::MODBUS_READ_OVERHEAD,
+    protocol::{RegisterBlock, WitProtocol},
+    serial::WitSerial,
+};
+use std::{collections::VecDeque, thread, time::Duration};
+
+/// Link used by `WitSensor` to exchange register data with a sensor
+///
+/// A transport owns both the port and the protocol encoding, so the sensor
+/// only deals with register reads, writes and the updates they produce.
+pub trait Transport {
+    /// Protocol spoken over this transport
+    fn protocol(&self) -> WitProtocol;
+
+    /// Send a request to read `count` registers starting at `start_register`
+    fn read_registers(&mut self, start_register: u16, count: u16) -> WitResult<()>;
+
+    /// Send a request to write a register value
+    fn write_register(&mut self, register: u16, value: u16) -> WitResult<()>;
+
+    /// Return the next block of register values received from the sensor
+    /// Returns None once no more input is available
+    fn receive(&mut self) -> WitResult<Option<RegisterBlock>>;
+
+    /// Discard any pending input and partially received frames
+    fn clear_input(&mut self) -> WitResult<()>;
+
+    /// Line speed in bits per second, None if reads are not paced by a UART
+    fn baud_rate(&self) -> Option<u32> {
+        None
+    }
+
+    /// Switch the line to another speed
+    /// Only transports paced by a UART support this
+    fn set_baud_rate(&mut self, baud_rate: u32) -> WitResult<()> {
+        Err(WitError::InvalidParameter(format!(
+            "Cannot switch a {:?} link to {} baud",
+            self.protocol(),
+            baud_rate
+        )))
+    }
+
+    /// Most registers answered by a single read request
+    fn registers_per_request(&self) -> u16 {
+        MAX_READ_COUNT
+    }
+
+    /// Bytes a read request and its answer take beyond the register values
+    fn request_overhead(&self) -> usize {
+        MODBUS_READ_OVERHEAD
+    }
+
+    /// Time `bytes` take on the wire, zero for links not paced by a UART
+    fn transmission_time(&self, _bytes: usize) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Limit how long `receive` waits for input
+    /// Links that never block ignore it
+    fn set_read_timeout(&mut self, _timeout: Duration) -> WitResult<()> {
+        Ok(())
+    }
+
+    /// Modbus slave address requests are sent to, None if the link has none
+    fn modbus_address(&self) -> Option<u8> {
+        None
+    }
+
+    /// Send later requests to another Modbus slave address
+    fn set_modbus_address(&mut self, address: u8) -> WitResult<()> {
+        Err(WitError::InvalidParameter(format!(
+            "Cannot address slave 0x{:02X} over a {:?} link",
+            address,
+            self.protocol()
+        )))
+    }
+
+    /// Send a raw command, for sensors driven by fixed byte sequences
+    fn send_command(&mut self, _command: &[u8]) -> WitResult<()> {
+        Err(WitError::InvalidParameter(format!(
+            "Raw commands cannot be sent over a {:?} link",
+            self.protocol()
+        )))
+    }
+}
+
+/// Serial transport speaking Modbus RTU or the WIT normal protocol
+pub struct SerialTransport {
+    serial: WitSerial,
+    protocol: WitProtocol,
+    modbus: ModbusProtocol,
+    normal: NormalProtocol,
+    pending: VecDeque<RegisterBlock>,
+}
+
+impl SerialTransport {
+    /// Create a serial transport on an open port
+    /// The slave address is only used by the Modbus protocols
+    pub fn new(serial: WitSerial, slave_address: u8, protocol: WitProtocol) -> Self {
+        Self {
+            serial,
+            protocol,
+            modbus: ModbusProtocol::new(slave_address),
+            normal: NormalProtocol::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Get the underlying serial port
+    pub fn serial(&self) -> &WitSerial {
+        &self.serial
+    }
+
+    /// Get the underlying serial port mutably
+    pub fn serial_mut(&mut self) -> &mut WitSerial {
+        &mut self.serial
+    }
+
+    /// Get the Modbus slave address requests are sent to
+    pub fn slave_address(&self) -> u8 {
+        self.modbus.slave_address()
+    }
+
+    /// Switch protocol and slave address, dropping any partially received data
+    pub fn reconfigure(&mut self, protocol: WitProtocol, slave_address: u8) {
+        self.protocol = protocol;
+        self.modbus = ModbusProtocol::new(slave_address);
+        self.normal.clear_buffer();
+        self.pending.clear();
+    }
+
+    /// Send raw bytes, waiting for them to leave the port
+    pub fn send_data(&mut self, data: &[u8]) -> WitResult<()> {
+        // Calculate transmission delay based on baud rate
+        let delay = self.serial.transmission_time(data.len()) + Duration::from_micros(300);
+
+        // Send data (no GPIO control needed for PC)
+        self.serial.write(data)?;
+        self.serial.flush()?;
+
+        // Wait for transmission to complete
+        thread::sleep(delay);
+
+        Ok(())
+    }
+}
+
+impl Transport for SerialTransport {
+    fn protocol(&self) -> WitProtocol {
+        self.protocol
+    }
+
+    fn read_registers(&mut self, start_register: u16, count: u16) -> WitResult<()> {
+        if self.protocol == WitProtocol::Jy61 {
+            return Err(jy61_unsupported());
+        }
+        if self.protocol.is_modbus() {
+            let request = self.modbus.generate_read_request(start_register, count);
+            return self.send_data(&request);
+        }
+
+        // Check the whole range first so nothing is sent for an invalid one
+        if start_register.checked_add(count.saturating_sub(1)).is_none() {
+            return Err(invalid_range(start_register, count));
+        }
+
+        // Each request returns four registers, so larger reads need several
+        for offset in (0..count).step_by(REGISTERS_PER_READ as usize) {
+            let request = self.normal.generate_read_request(start_register + offset);
+            self.send_data(&request)?;
+        }
+        Ok(())
+    }
+
+    fn write_register(&mut self, register: u16, value: u16) -> WitResult<()> {
+        if self.protocol == WitProtocol::Jy61 {
+            return Err(jy61_unsupported());
+        }
+        let request = if self.protocol.is_modbus() {
+            self.modbus.generate_write_request(register, value)
+        } else {
+            self.normal.generate_write_request(register, value)
+        };
+        self.send_data(&request)
+    }
+
+    fn receive(&mut self) -> WitResult<Option<RegisterBlock>> {
+        while self.pending.is_empty() {
+            let Some(byte) = self.serial.read_byte()? else {
+                break;
+            };
+
+            if self.protocol.is_modbus() {
+                if let Some(block) = self.modbus.process_byte(byte)? {
+                    self.pending.push_back(block);
+                }
+            } else if let Some(blocks) = self.normal.process_byte(byte)? {
+                self.pending.extend(blocks);
+            }
+
+            // Reset buffer if it gets too large
+            if self.modbus.should_reset_buffer() {
+                self.modbus.clear_buffer();
+            }
+            if self.normal.should_reset_buffer() {
+                self.normal.clear_buffer();
+            }
+        }
+
+        Ok(self.pending.pop_front())
+    }
+
+    fn baud_rate(&self) -> Option<u32> {
+        Some(self.serial.baud_rate())
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> WitResult<()> {
+        self.serial.set_baud_rate(baud_rate)
+    }
+
+    fn registers_per_request(&self) -> u16 {
+        if self.protocol.is_modbus() {
+            MAX_READ_COUNT
+        } else {
+            REGISTERS_PER_READ
+        }
+    }
+
+    fn request_overhead(&self) -> usize {
+        if self.protocol.is_modbus() {
+            MODBUS_READ_OVERHEAD
+        } else {
+            // 5-byte command, answered by a packet holding four registers
+            5 + PACKET_LENGTH - 2 * REGISTERS_PER_READ as usize
+        }
+    }
+
+    fn transmission_time(&self, bytes: usize) -> Duration {
+        self.serial.transmission_time(bytes)
+    }
+
+    fn set_read_timeout(&mut self, timeout: Duration) -> WitResult<()> {
+        self.serial.set_read_timeout(timeout)
+    }
+
+    fn modbus_address(&self) -> Option<u8> {
+        self.protocol.is_modbus().then(|| self.slave_address())
+    }
+
+    fn set_modbus_address(&mut self, address: u8) -> WitResult<()> {
+        self.reconfigure(self.protocol, address);
+        Ok(())
+    }
+
+    fn send_command(&mut self, command: &[u8]) -> WitResult<()> {
+        self.send_data(command)
+    }
+
+    fn clear_input(&mut self) -> WitResult<()> {
+        self.serial.clear_input_buffer()?;
+        self.modbus.clear_buffer();
+        self.normal.clear_buffer();
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Error for a read running past the last register address
+pub(crate) fn invalid_range(start_register: u16, count: u16) -> WitError {
+    WitError::InvalidParameter(format!("Invalid register range: 0x{:02X} + {}", start_register, count))
+}
+
+/// JY61 sensors have no register access, only fixed commands
+fn jy61_unsupported() -> WitError {
+    WitError::InvalidParameter("JY61 sensors only accept Jy61Command commands".to_string())
+}